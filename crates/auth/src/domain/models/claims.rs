@@ -11,19 +11,51 @@ pub struct Scope(pub String);
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Subject(pub String);
 
+/// The `aud` claim, which may be a single string or an array of strings.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Audience {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl Audience {
+    pub fn contains(&self, audience: &str) -> bool {
+        match self {
+            Audience::Single(aud) => aud == audience,
+            Audience::Multiple(auds) => auds.iter().any(|aud| aud == audience),
+        }
+    }
+}
+
+impl From<String> for Audience {
+    fn from(audience: String) -> Self {
+        Audience::Single(audience)
+    }
+}
+
+impl PartialEq<&str> for Audience {
+    fn eq(&self, other: &&str) -> bool {
+        matches!(self, Audience::Single(aud) if aud == other)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: Subject,
     pub iss: String,
-    pub aud: Option<String>,
+    pub aud: Option<Audience>,
     pub exp: Option<i64>,
 
     pub email: Option<String>,
+    #[serde(default)]
     pub email_verified: bool,
     pub name: Option<String>,
+    #[serde(default)]
     pub preferred_username: String,
     pub given_name: Option<String>,
     pub family_name: Option<String>,
+    #[serde(default)]
     pub scope: String,
     pub client_id: Option<String>,
 
@@ -31,6 +63,43 @@ pub struct Claims {
     pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
+impl Claims {
+    /// Realm roles, read from the Keycloak `realm_access.roles` claim.
+    ///
+    /// Missing or malformed role claims yield no roles.
+    pub fn realm_roles(&self) -> Vec<String> {
+        Self::roles_of(self.extra.get("realm_access"))
+    }
+
+    /// Roles granted on `resource`, read from the Keycloak
+    /// `resource_access.<resource>.roles` claim.
+    pub fn resource_roles(&self, resource: &str) -> Vec<String> {
+        Self::roles_of(
+            self.extra
+                .get("resource_access")
+                .and_then(|access| access.get(resource)),
+        )
+    }
+
+    /// Scopes of the token, split from the space-separated `scope` claim.
+    pub fn scopes(&self) -> Vec<String> {
+        self.scope.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn roles_of(access: Option<&serde_json::Value>) -> Vec<String> {
+        access
+            .and_then(|access| access.get("roles"))
+            .and_then(|roles| roles.as_array())
+            .map(|roles| {
+                roles
+                    .iter()
+                    .filter_map(|role| role.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Jwt {
     pub claims: Claims,
@@ -39,7 +108,7 @@ pub struct Jwt {
 
 #[cfg(test)]
 mod tests {
-    use crate::domain::models::claims::{Claims, Role, Scope, Subject};
+    use crate::domain::models::claims::{Audience, Claims, Role, Scope, Subject};
 
     #[test]
     fn test_subject_deserialize_from_json() {
@@ -112,6 +181,72 @@ mod tests {
         assert_eq!(claims.exp.unwrap(), 1761117956);
     }
 
+    #[test]
+    fn test_claims_single_audience() {
+        let json = r#"{
+            "sub": "user-123",
+            "iss": "https://auth.beep.com",
+            "aud": "beep-api"
+        }"#;
+
+        let claims: Claims = serde_json::from_str(json).unwrap();
+        let aud = claims.aud.unwrap();
+
+        assert_eq!(aud, Audience::Single("beep-api".to_string()));
+        assert!(aud.contains("beep-api"));
+        assert!(!aud.contains("account"));
+    }
+
+    #[test]
+    fn test_claims_multiple_audiences() {
+        let json = r#"{
+            "sub": "user-123",
+            "iss": "https://auth.beep.com",
+            "aud": ["account", "beep-api"]
+        }"#;
+
+        let claims: Claims = serde_json::from_str(json).unwrap();
+        let aud = claims.aud.unwrap();
+
+        assert!(aud.contains("beep-api"));
+        assert!(aud.contains("account"));
+        assert!(!aud.contains("other"));
+    }
+
+    #[test]
+    fn test_claims_roles_and_scopes() {
+        let json = r#"{
+            "sub": "user-123",
+            "iss": "https://auth.beep.com",
+            "scope": "profile email",
+            "realm_access": { "roles": ["user", "moderator"] },
+            "resource_access": { "account": { "roles": ["view-profile"] } }
+        }"#;
+
+        let claims: Claims = serde_json::from_str(json).unwrap();
+
+        assert_eq!(claims.realm_roles(), vec!["user", "moderator"]);
+        assert_eq!(claims.resource_roles("account"), vec!["view-profile"]);
+        assert!(claims.resource_roles("unknown").is_empty());
+        assert_eq!(claims.scopes(), vec!["profile", "email"]);
+    }
+
+    #[test]
+    fn test_claims_missing_optional_fields() {
+        let json = r#"{
+            "sub": "service-123",
+            "iss": "https://auth.beep.com",
+            "realm_access": { "roles": "not-a-list" }
+        }"#;
+
+        let claims: Claims = serde_json::from_str(json).unwrap();
+
+        assert_eq!(claims.preferred_username, "");
+        assert!(!claims.email_verified);
+        assert!(claims.realm_roles().is_empty());
+        assert!(claims.scopes().is_empty());
+    }
+
     #[test]
     fn test_claims_with_extra_fields() {
         let json = r#"{
@@ -6,4 +6,7 @@ pub struct Client {
     pub client_id: String,
     pub roles: Vec<String>,
     pub scopes: Vec<String>,
+    /// Expiry of the token, as a unix timestamp (the `exp` claim).
+    #[serde(default)]
+    pub expires_at: Option<i64>,
 }
@@ -1,6 +1,6 @@
 use thiserror::Error;
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum AuthError {
     #[error("invalid token: {message}")]
     InvalidToken { message: String },
@@ -19,4 +19,13 @@ pub enum AuthError {
 
     #[error("token expired")]
     Expired,
+
+    #[error("invalid audience: expected {expected}")]
+    InvalidAudience { expected: String },
+
+    #[error("client credentials not configured")]
+    MissingClientCredentials,
+
+    #[error("token endpoint: {error}: {description}")]
+    TokenEndpoint { error: String, description: String },
 }
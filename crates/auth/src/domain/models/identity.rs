@@ -1,3 +1,6 @@
+use std::time::Duration;
+
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 
 use crate::domain::models::{claims::Claims, client::Client, user::User};
@@ -41,24 +44,55 @@ impl Identity {
     pub fn has_role(&self, role: &str) -> bool {
         self.roles().iter().any(|r| r == role)
     }
+
+    /// Expiry of the token the identity was built from, as a unix timestamp.
+    pub fn expires_at(&self) -> Option<i64> {
+        match self {
+            Identity::User(u) => u.expires_at,
+            Identity::Client(c) => c.expires_at,
+        }
+    }
+
+    /// Whether the token has expired. Identities without an expiry never
+    /// expire: repositories reject tokens lacking one, as
+    /// [`KeycloakAuthRepository`](crate::KeycloakAuthRepository) does, when
+    /// they must not be accepted forever.
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_with_leeway(Duration::ZERO)
+    }
+
+    /// Same as [`Self::is_expired`], tolerating `leeway` of clock skew
+    /// between the identity provider and this service.
+    pub fn is_expired_with_leeway(&self, leeway: Duration) -> bool {
+        let leeway = i64::try_from(leeway.as_secs()).unwrap_or(i64::MAX);
+
+        self.expires_at()
+            .is_some_and(|exp| exp.saturating_add(leeway) < Utc::now().timestamp())
+    }
 }
 
 impl From<Claims> for Identity {
     fn from(claims: Claims) -> Self {
+        let roles = claims.realm_roles();
+        let scopes = claims.scopes();
+        let expires_at = claims.exp;
+
         if let Some(client_id) = claims.client_id {
             Identity::Client(Client {
                 id: claims.sub.0,
-                client_id: client_id,
-                roles: Vec::new(),
-                scopes: Vec::new(),
+                client_id,
+                roles,
+                scopes,
+                expires_at,
             })
         } else {
             Identity::User(User {
                 id: claims.sub.0,
                 email: claims.email,
                 name: claims.name,
-                roles: Vec::new(),
+                roles,
                 username: claims.preferred_username,
+                expires_at,
             })
         }
     }
@@ -66,6 +100,9 @@ impl From<Claims> for Identity {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
     use serde_json::json;
 
     use crate::domain::models::{claims::Claims, identity::Identity};
@@ -74,7 +111,7 @@ mod tests {
         Claims {
             sub: crate::domain::models::claims::Subject("user-123".to_string()),
             iss: "https://auth.beep.com".to_string(),
-            aud: Some("beep-api".to_string()),
+            aud: Some("beep-api".to_string().into()),
             email: Some("john.doe@example.com".to_string()),
             email_verified: true,
             exp: None,
@@ -101,7 +138,7 @@ mod tests {
         Claims {
             sub: crate::domain::models::claims::Subject("service-123".to_string()),
             iss: "https://auth.beep.com".to_string(),
-            aud: Some("beep-api".to_string()),
+            aud: Some("beep-api".to_string().into()),
             email: None,
             email_verified: false,
             name: None,
@@ -135,11 +172,20 @@ mod tests {
                 assert_eq!(user.username, "johndoe");
                 assert_eq!(user.email, Some("john.doe@example.com".to_string()));
                 assert_eq!(user.name, Some("John Doe".to_string()));
+                assert_eq!(user.roles, vec!["user", "moderator"]);
             }
             Identity::Client(_) => panic!("Expected User, got Client"),
         }
     }
 
+    #[test]
+    fn test_identity_has_role() {
+        let identity: Identity = create_user_claims().into();
+
+        assert!(identity.has_role("moderator"));
+        assert!(!identity.has_role("admin"));
+    }
+
     #[test]
     fn test_claims_to_identity_service_account() {
         let claims = create_service_account_claims();
@@ -149,8 +195,41 @@ mod tests {
             Identity::Client(client) => {
                 assert_eq!(client.id, "service-123");
                 assert_eq!(client.client_id, "beep-bot");
+                assert_eq!(client.roles, vec!["service", "bot"]);
+                assert_eq!(
+                    client.scopes,
+                    vec!["admin:all", "read:users", "write:messages"]
+                );
             }
             Identity::User(_) => panic!("Expected Client, got User"),
         }
     }
+
+    #[test]
+    fn test_identity_expiry() {
+        let now = Utc::now().timestamp();
+        let expired: Identity = Claims {
+            exp: Some(now - 10),
+            ..create_user_claims()
+        }
+        .into();
+        let valid: Identity = Claims {
+            exp: Some(now + 60),
+            ..create_user_claims()
+        }
+        .into();
+
+        assert_eq!(valid.expires_at(), Some(now + 60));
+        assert!(!valid.is_expired());
+        assert!(expired.is_expired());
+        assert!(!expired.is_expired_with_leeway(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_identity_without_expiry_never_expires() {
+        let identity: Identity = create_user_claims().into();
+
+        assert_eq!(identity.expires_at(), None);
+        assert!(!identity.is_expired());
+    }
 }
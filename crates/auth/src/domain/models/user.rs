@@ -7,4 +7,7 @@ pub struct User {
     pub email: Option<String>,
     pub name: Option<String>,
     pub roles: Vec<String>,
+    /// Expiry of the token, as a unix timestamp (the `exp` claim).
+    #[serde(default)]
+    pub expires_at: Option<i64>,
 }
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use crate::domain::models::{claims::Claims, errors::AuthError, identity::Identity};
 
 pub trait AuthRepository: Send + Sync {
@@ -5,6 +7,27 @@ pub trait AuthRepository: Send + Sync {
     -> impl Future<Output = Result<Claims, AuthError>> + Send;
 
     fn identify(&self, token: &str) -> impl Future<Output = Result<Identity, AuthError>> + Send;
+
+    /// Identify a batch of tokens. Results are aligned with `tokens`.
+    ///
+    /// The default implementation calls [`Self::identify`] sequentially.
+    fn identify_many(
+        &self,
+        tokens: &[&str],
+    ) -> impl Future<Output = Vec<Result<Identity, AuthError>>> + Send {
+        async move {
+            let mut identities = Vec::with_capacity(tokens.len());
+            for token in tokens {
+                identities.push(self.identify(token).await);
+            }
+            identities
+        }
+    }
+
+    /// Clock skew tolerated when checking token expiry.
+    fn leeway(&self) -> Duration {
+        Duration::ZERO
+    }
 }
 
 pub trait HasAuthRepository {
@@ -0,0 +1,87 @@
+use std::sync::{Arc, Mutex};
+
+use crate::domain::models::errors::AuthError;
+
+/// Test hook making a repository fail deterministically.
+///
+/// Only compiled with the `failure-injection` feature. Clones share the same
+/// state, so a hook configured on a cloned repository affects every clone.
+#[derive(Debug, Clone, Default)]
+pub struct FailureInjector {
+    inner: Arc<Mutex<Option<(AuthError, usize)>>>,
+}
+
+impl FailureInjector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make the next `calls` calls fail with `error`.
+    pub fn fail_next(&self, error: AuthError, calls: usize) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        *inner = (calls > 0).then_some((error, calls));
+    }
+
+    /// Drop any pending injected failure.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        *inner = None;
+    }
+
+    /// Number of calls that are still going to fail.
+    pub fn remaining(&self) -> usize {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.as_ref().map(|(_, calls)| *calls).unwrap_or(0)
+    }
+
+    pub(crate) fn check(&self) -> Result<(), AuthError> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+
+        let Some((error, calls)) = inner.as_mut() else {
+            return Ok(());
+        };
+
+        let error = error.clone();
+        *calls -= 1;
+
+        if *calls == 0 {
+            *inner = None;
+        }
+
+        Err(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::models::errors::AuthError;
+    use crate::infrastructure::failure_injection::FailureInjector;
+
+    #[test]
+    fn test_fail_next_returns_error_for_configured_calls() {
+        let injector = FailureInjector::new();
+        injector.fail_next(
+            AuthError::Network {
+                message: "connection refused".to_string(),
+            },
+            2,
+        );
+
+        assert!(matches!(injector.check(), Err(AuthError::Network { .. })));
+        assert_eq!(injector.remaining(), 1);
+        assert!(matches!(injector.check(), Err(AuthError::Network { .. })));
+        assert!(injector.check().is_ok());
+    }
+
+    #[test]
+    fn test_clear_and_clones_share_state() {
+        let injector = FailureInjector::new();
+        let clone = injector.clone();
+
+        injector.fail_next(AuthError::Expired, 5);
+        assert_eq!(clone.remaining(), 5);
+
+        clone.clear();
+        assert!(injector.check().is_ok());
+    }
+}
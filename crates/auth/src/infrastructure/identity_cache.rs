@@ -0,0 +1,119 @@
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+};
+
+use lru::LruCache;
+use sha2::{Digest, Sha256};
+
+use crate::domain::models::identity::Identity;
+
+pub const DEFAULT_IDENTITY_CACHE_CAPACITY: usize = 1024;
+
+type TokenHash = [u8; 32];
+
+#[derive(Debug, Clone)]
+struct CachedIdentity {
+    identity: Identity,
+    expires_at: i64,
+}
+
+/// Bounded LRU cache of validated identities, keyed by the SHA-256 of the
+/// token so raw tokens are never kept in memory.
+///
+/// Entries expire with the token's `exp` claim. LRU lookups update recency,
+/// hence the `Mutex` rather than a `RwLock`.
+#[derive(Debug, Clone)]
+pub(crate) struct IdentityCache {
+    entries: Option<Arc<Mutex<LruCache<TokenHash, CachedIdentity>>>>,
+}
+
+impl IdentityCache {
+    /// Create a cache holding at most `capacity` identities. A capacity of 0
+    /// disables caching.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            entries: NonZeroUsize::new(capacity)
+                .map(|capacity| Arc::new(Mutex::new(LruCache::new(capacity)))),
+        }
+    }
+
+    pub(crate) fn get(&self, token: &str, now: i64) -> Option<Identity> {
+        let mut entries = self.entries.as_ref()?.lock().ok()?;
+        let key = Self::key(token);
+
+        match entries.get(&key) {
+            Some(cached) if cached.expires_at > now => Some(cached.identity.clone()),
+            Some(_) => {
+                entries.pop(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub(crate) fn insert(&self, token: &str, identity: Identity, expires_at: i64) {
+        let Some(mut entries) = self.entries.as_ref().and_then(|e| e.lock().ok()) else {
+            return;
+        };
+
+        entries.put(
+            Self::key(token),
+            CachedIdentity {
+                identity,
+                expires_at,
+            },
+        );
+    }
+
+    fn key(token: &str) -> TokenHash {
+        Sha256::digest(token.as_bytes()).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::models::{client::Client, identity::Identity};
+    use crate::infrastructure::identity_cache::IdentityCache;
+
+    fn identity(id: &str) -> Identity {
+        Identity::Client(Client {
+            id: id.to_string(),
+            client_id: "beep-bot".to_string(),
+            roles: Vec::new(),
+            scopes: Vec::new(),
+            expires_at: None,
+        })
+    }
+
+    #[test]
+    fn test_cache_hit_until_expiry() {
+        let cache = IdentityCache::new(8);
+        cache.insert("token", identity("service-123"), 100);
+
+        assert_eq!(cache.get("token", 99), Some(identity("service-123")));
+        assert_eq!(cache.get("token", 100), None);
+        assert_eq!(cache.get("other", 0), None);
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let cache = IdentityCache::new(2);
+        cache.insert("a", identity("a"), 100);
+        cache.insert("b", identity("b"), 100);
+        cache.get("a", 0);
+        cache.insert("c", identity("c"), 100);
+
+        assert!(cache.get("a", 0).is_some());
+        assert!(cache.get("b", 0).is_none());
+        assert!(cache.get("c", 0).is_some());
+    }
+
+    #[test]
+    fn test_zero_capacity_disables_cache() {
+        let cache = IdentityCache::new(0);
+        cache.insert("token", identity("service-123"), 100);
+
+        assert_eq!(cache.get("token", 0), None);
+    }
+}
@@ -1,13 +1,49 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
 
 use crate::domain::{
-    models::{claims::Claims, errors::AuthError, identity::Identity},
+    models::{claims::Claims, errors::AuthError, identity::Identity, token::Token},
     ports::AuthRepository,
 };
+#[cfg(feature = "failure-injection")]
+use crate::infrastructure::failure_injection::FailureInjector;
+use crate::infrastructure::identity_cache::{DEFAULT_IDENTITY_CACHE_CAPACITY, IdentityCache};
 use chrono::Utc;
-use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+use futures_util::{StreamExt, stream};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header, errors::ErrorKind};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+/// Time allowed to open a connection to Keycloak.
+const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time allowed for a whole request to Keycloak, so an unresponsive issuer
+/// cannot hang token validation.
+const HTTP_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Minimum delay between two JWKS fetch attempts, so tokens carrying random
+/// `kid`s, or an unreachable issuer, cannot make every request hit the
+/// network.
+const JWKS_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Age after which the cached JWKS is refetched, so keys rotated out of the
+/// realm stop being trusted.
+const JWKS_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// Maximum number of tokens validated concurrently by
+/// [`AuthRepository::identify_many`].
+const IDENTIFY_MANY_CONCURRENCY: usize = 16;
+
+/// Default clock skew tolerated when checking token expiry.
+pub const DEFAULT_TOKEN_LEEWAY: Duration = Duration::from_secs(30);
+
+/// Service tokens are refreshed this long before they actually expire.
+const SERVICE_TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(30);
 
 #[derive(Debug, Serialize, Deserialize)]
 struct Jwks {
@@ -21,25 +57,319 @@ struct Jwk {
     e: String,
 }
 
+/// How [`KeycloakAuthRepository`] validates access tokens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValidationStrategy {
+    /// Verify JWTs locally against the realm's JWKS.
+    #[default]
+    Jwt,
+    /// Ask Keycloak's introspection endpoint, e.g. for opaque reference
+    /// tokens. Requires client credentials.
+    Introspection,
+}
+
+/// Subset of the issuer's OpenID Connect discovery document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenIdConfiguration {
+    jwks_uri: String,
+    token_endpoint: String,
+    introspection_endpoint: Option<String>,
+}
+
+#[derive(Clone)]
+struct ClientCredentials {
+    client_id: String,
+    client_secret: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenErrorResponse {
+    error: String,
+    #[serde(default)]
+    error_description: String,
+}
+
+struct CachedServiceToken {
+    token: Token,
+    refresh_at: Instant,
+}
+
+/// Signing keys of the realm, indexed by key id.
+#[derive(Default)]
+struct JwksCache {
+    keys: HashMap<String, DecodingKey>,
+    /// Last successful fetch.
+    fetched_at: Option<Instant>,
+    /// Last fetch attempt, successful or not.
+    attempted_at: Option<Instant>,
+}
+
+impl JwksCache {
+    /// Whether the keys are older than [`JWKS_MAX_AGE`] and no fetch was
+    /// attempted within [`JWKS_MIN_REFRESH_INTERVAL`].
+    fn is_stale(&self) -> bool {
+        self.fetched_at
+            .is_none_or(|at| at.elapsed() >= JWKS_MAX_AGE)
+            && !self.recently_attempted()
+    }
+
+    fn recently_attempted(&self) -> bool {
+        self.attempted_at
+            .is_some_and(|at| at.elapsed() < JWKS_MIN_REFRESH_INTERVAL)
+    }
+
+    fn last_attempt_failed(&self) -> bool {
+        self.fetched_at < self.attempted_at
+    }
+}
+
 #[derive(Clone)]
 pub struct KeycloakAuthRepository {
     pub http: Arc<Client>,
     pub issuer: String,
+    /// Audience tokens must be issued for. `None` disables the check, e.g.
+    /// for gateways forwarding tokens audienced for another service.
     pub audience: Option<String>,
+    openid_configuration: Arc<RwLock<Option<OpenIdConfiguration>>>,
+    jwks: Arc<RwLock<JwksCache>>,
+    /// Held while fetching the JWKS, so concurrent misses share one fetch.
+    jwks_refresh: Arc<Mutex<()>>,
+    identities: IdentityCache,
+    client_credentials: Option<ClientCredentials>,
+    validation_strategy: ValidationStrategy,
+    leeway: Duration,
+    service_token: Arc<RwLock<Option<CachedServiceToken>>>,
+    #[cfg(feature = "failure-injection")]
+    pub failure_injector: FailureInjector,
 }
 
 impl KeycloakAuthRepository {
     pub fn new(issuer: impl Into<String>, audience: Option<String>) -> Self {
         Self {
-            http: Arc::new(Client::new()),
+            http: Arc::new(
+                Client::builder()
+                    .connect_timeout(HTTP_CONNECT_TIMEOUT)
+                    .timeout(HTTP_REQUEST_TIMEOUT)
+                    .build()
+                    .expect("failed to build the keycloak http client"),
+            ),
             issuer: issuer.into(),
             audience,
+            openid_configuration: Arc::new(RwLock::new(None)),
+            jwks: Arc::new(RwLock::new(JwksCache::default())),
+            jwks_refresh: Arc::new(Mutex::new(())),
+            identities: IdentityCache::new(DEFAULT_IDENTITY_CACHE_CAPACITY),
+            client_credentials: None,
+            validation_strategy: ValidationStrategy::default(),
+            leeway: DEFAULT_TOKEN_LEEWAY,
+            service_token: Arc::new(RwLock::new(None)),
+            #[cfg(feature = "failure-injection")]
+            failure_injector: FailureInjector::new(),
         }
     }
 
-    async fn fetch_jwks(&self) -> Result<Jwks, AuthError> {
-        let url = format!("{}/protocol/openid-connect/certs", self.issuer);
+    /// Bound the number of validated identities kept in memory. Repeated
+    /// calls to [`AuthRepository::identify`] with a cached, unexpired token
+    /// skip validation. A capacity of 0 disables the cache.
+    ///
+    /// The cache is bypassed with [`ValidationStrategy::Introspection`], so
+    /// revoked tokens are rejected immediately.
+    pub fn with_identity_cache_capacity(mut self, capacity: usize) -> Self {
+        self.identities = IdentityCache::new(capacity);
+        self
+    }
+
+    /// Credentials of the service account used by [`Self::service_token`].
+    pub fn with_client_credentials(
+        mut self,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        self.client_credentials = Some(ClientCredentials {
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+        });
+        self
+    }
+
+    /// Select how tokens are validated. Defaults to [`ValidationStrategy::Jwt`].
+    pub fn with_validation_strategy(mut self, strategy: ValidationStrategy) -> Self {
+        self.validation_strategy = strategy;
+        self
+    }
+
+    /// Clock skew tolerated between Keycloak and this service when checking
+    /// token expiry. Defaults to [`DEFAULT_TOKEN_LEEWAY`].
+    pub fn with_leeway(mut self, leeway: Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// Access token of the service account, obtained through the OAuth2
+    /// client credentials grant.
+    ///
+    /// The token is cached and only requested again shortly before it
+    /// expires.
+    pub async fn service_token(&self) -> Result<Token, AuthError> {
+        if let Some(cached) = self
+            .service_token
+            .read()
+            .map_err(|e| AuthError::Internal {
+                message: e.to_string(),
+            })?
+            .as_ref()
+            && cached.refresh_at > Instant::now()
+        {
+            return Ok(cached.token.clone());
+        }
+
+        let credentials = self
+            .client_credentials
+            .as_ref()
+            .ok_or(AuthError::MissingClientCredentials)?;
+        let config = self.openid_configuration().await?;
+
+        let response: TokenResponse = self
+            .post_form(
+                &config.token_endpoint,
+                &[
+                    ("grant_type", "client_credentials"),
+                    ("client_id", credentials.client_id.as_str()),
+                    ("client_secret", credentials.client_secret.as_str()),
+                ],
+            )
+            .await?;
+
+        let token = Token::new(response.access_token);
+        let refresh_at = Instant::now()
+            + Duration::from_secs(response.expires_in).saturating_sub(SERVICE_TOKEN_EXPIRY_MARGIN);
+
+        *self
+            .service_token
+            .write()
+            .map_err(|e| AuthError::Internal {
+                message: e.to_string(),
+            })? = Some(CachedServiceToken {
+            token: token.clone(),
+            refresh_at,
+        });
+
+        Ok(token)
+    }
+
+    /// Verify the signature and issuer of a JWT against the realm's JWKS.
+    async fn validate_jwt(&self, token: &str) -> Result<Claims, AuthError> {
+        let header = decode_header(token).map_err(|e| AuthError::InvalidToken {
+            message: e.to_string(),
+        })?;
+
+        let kid = header.kid.ok_or_else(|| AuthError::InvalidToken {
+            message: "missing kind".into(),
+        })?;
+
+        let decoding_key = self.decoding_key(&kid).await?;
+
+        let mut validation = Validation::new(Algorithm::RS256);
+
+        validation.set_issuer(&[&self.issuer]);
+        validation.validate_aud = false;
+        validation.leeway = self.leeway.as_secs();
+
+        let data =
+            decode::<Claims>(token, &decoding_key, &validation).map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => AuthError::Expired,
+                _ => AuthError::InvalidToken {
+                    message: e.to_string(),
+                },
+            })?;
 
+        Ok(data.claims)
+    }
+
+    /// Validate a token through the realm's introspection endpoint. Inactive
+    /// tokens are rejected.
+    async fn introspect(&self, token: &str) -> Result<Claims, AuthError> {
+        let credentials = self
+            .client_credentials
+            .as_ref()
+            .ok_or(AuthError::MissingClientCredentials)?;
+        let config = self.openid_configuration().await?;
+        let url = config
+            .introspection_endpoint
+            .unwrap_or_else(|| format!("{}/protocol/openid-connect/token/introspect", self.issuer));
+
+        let response: serde_json::Value = self
+            .post_form(
+                &url,
+                &[
+                    ("token", token),
+                    ("client_id", credentials.client_id.as_str()),
+                    ("client_secret", credentials.client_secret.as_str()),
+                ],
+            )
+            .await?;
+
+        if !response
+            .get("active")
+            .and_then(|a| a.as_bool())
+            .unwrap_or(false)
+        {
+            return Err(AuthError::InvalidToken {
+                message: "token is not active".to_string(),
+            });
+        }
+
+        serde_json::from_value(response).map_err(|e| AuthError::InvalidToken {
+            message: format!("failed to deserialize introspection claims: {}", e),
+        })
+    }
+
+    /// POST a form to a Keycloak endpoint, turning OAuth2 error responses
+    /// into [`AuthError::TokenEndpoint`].
+    async fn post_form<T: for<'de> Deserialize<'de>>(
+        &self,
+        url: &str,
+        form: &[(&str, &str)],
+    ) -> Result<T, AuthError> {
+        let resp = self
+            .http
+            .post(url)
+            .form(form)
+            .send()
+            .await
+            .map_err(|e| AuthError::Network {
+                message: e.to_string(),
+            })?;
+
+        let status = resp.status();
+        let bytes = resp.bytes().await.map_err(|e| AuthError::Network {
+            message: e.to_string(),
+        })?;
+
+        if !status.is_success() {
+            return Err(match serde_json::from_slice::<TokenErrorResponse>(&bytes) {
+                Ok(e) => AuthError::TokenEndpoint {
+                    error: e.error,
+                    description: e.error_description,
+                },
+                Err(_) => AuthError::Network {
+                    message: format!("failed to post to {}: {}", url, status),
+                },
+            });
+        }
+
+        serde_json::from_slice(&bytes).map_err(|e| AuthError::Network {
+            message: e.to_string(),
+        })
+    }
+
+    async fn fetch_json<T: for<'de> Deserialize<'de>>(&self, url: &str) -> Result<T, AuthError> {
         let resp = self
             .http
             .get(url)
@@ -51,7 +381,7 @@ impl KeycloakAuthRepository {
 
         if resp.status().is_client_error() || resp.status().is_server_error() {
             return Err(AuthError::Network {
-                message: format!("failed to fetch jwks: {}", resp.status()),
+                message: format!("failed to fetch {}: {}", url, resp.status()),
             });
         }
 
@@ -59,56 +389,173 @@ impl KeycloakAuthRepository {
             message: e.to_string(),
         })?;
 
-        let jwks: Jwks = serde_json::from_slice(&bytes).map_err(|e| AuthError::Network {
+        serde_json::from_slice(&bytes).map_err(|e| AuthError::Network {
             message: e.to_string(),
-        })?;
+        })
+    }
+
+    /// Discovery document of the issuer, fetched once and then cached.
+    async fn openid_configuration(&self) -> Result<OpenIdConfiguration, AuthError> {
+        if let Some(config) = self.read_openid_configuration()?.clone() {
+            return Ok(config);
+        }
 
-        Ok(jwks)
+        let url = format!("{}/.well-known/openid-configuration", self.issuer);
+        let config: OpenIdConfiguration = self.fetch_json(&url).await?;
+
+        *self
+            .openid_configuration
+            .write()
+            .map_err(|e| AuthError::Internal {
+                message: e.to_string(),
+            })? = Some(config.clone());
+
+        Ok(config)
     }
-}
 
-impl AuthRepository for KeycloakAuthRepository {
-    async fn validate_token(
+    fn read_openid_configuration(
         &self,
-        token: &str,
-    ) -> Result<crate::domain::models::claims::Claims, AuthError> {
-        let header = decode_header(token).map_err(|e| AuthError::InvalidToken {
-            message: e.to_string(),
-        })?;
+    ) -> Result<std::sync::RwLockReadGuard<'_, Option<OpenIdConfiguration>>, AuthError> {
+        self.openid_configuration
+            .read()
+            .map_err(|e| AuthError::Internal {
+                message: e.to_string(),
+            })
+    }
 
-        let kid = header.kid.ok_or_else(|| AuthError::InvalidToken {
-            message: "missing kind".into(),
-        })?;
+    async fn fetch_jwks(&self) -> Result<Jwks, AuthError> {
+        let config = self.openid_configuration().await?;
 
-        let jwks = self.fetch_jwks().await?;
+        self.fetch_json(&config.jwks_uri).await
+    }
+
+    /// Refetch the realm signing keys, unless a fetch was attempted less
+    /// than [`JWKS_MIN_REFRESH_INTERVAL`] ago. Should that attempt have
+    /// failed, fail again without hitting the network.
+    ///
+    /// Only one fetch runs at a time: concurrent callers wait for it and then
+    /// reuse its outcome.
+    async fn refresh_jwks(&self) -> Result<(), AuthError> {
+        let _refreshing = self.jwks_refresh.lock().await;
 
-        let keys = jwks.keys;
+        {
+            let mut cache = self.write_jwks()?;
+
+            if cache.recently_attempted() {
+                if cache.last_attempt_failed() {
+                    return Err(AuthError::Network {
+                        message: "jwks unavailable: last fetch failed".to_string(),
+                    });
+                }
+                return Ok(());
+            }
+
+            cache.attempted_at = Some(Instant::now());
+        }
 
-        let key = keys
+        debug!("keycloak: fetching jwks for {}", self.issuer);
+
+        let jwks = self.fetch_jwks().await?;
+
+        let keys = jwks
+            .keys
             .iter()
-            .find(|k| k.kid == kid)
-            .ok_or_else(|| AuthError::KeyNotFound { key: kid.clone() })?;
+            .filter_map(|k| {
+                DecodingKey::from_rsa_components(&k.n, &k.e)
+                    .ok()
+                    .map(|key| (k.kid.clone(), key))
+            })
+            .collect();
 
-        let decoding_key =
-            DecodingKey::from_rsa_components(&key.n, &key.e).map_err(|e| AuthError::Internal {
-                message: e.to_string(),
-            })?;
+        let mut cache = self.write_jwks()?;
+        cache.keys = keys;
+        cache.fetched_at = Some(Instant::now());
 
-        let mut validation = Validation::new(Algorithm::RS256);
+        Ok(())
+    }
 
-        validation.validate_aud = false;
+    fn write_jwks(&self) -> Result<std::sync::RwLockWriteGuard<'_, JwksCache>, AuthError> {
+        self.jwks.write().map_err(|e| AuthError::Internal {
+            message: e.to_string(),
+        })
+    }
 
-        let data = decode::<Claims>(token, &decoding_key, &validation).map_err(|e| {
-            AuthError::InvalidToken {
-                message: e.to_string(),
+    fn read_jwks(&self) -> Result<std::sync::RwLockReadGuard<'_, JwksCache>, AuthError> {
+        self.jwks.read().map_err(|e| AuthError::Internal {
+            message: e.to_string(),
+        })
+    }
+
+    /// Decoding key for `kid`, from the cached JWKS or, on a miss, from a
+    /// freshly fetched one.
+    ///
+    /// A JWKS older than [`JWKS_MAX_AGE`] is replaced first, dropping the
+    /// keys Keycloak no longer publishes. Should that refetch fail, the stale
+    /// keys keep being used rather than rejecting every token while the
+    /// issuer is unreachable, and the refetch is only retried after
+    /// [`JWKS_MIN_REFRESH_INTERVAL`].
+    async fn decoding_key(&self, kid: &str) -> Result<DecodingKey, AuthError> {
+        if self.read_jwks()?.is_stale()
+            && let Err(e) = self.refresh_jwks().await
+        {
+            if self.read_jwks()?.keys.is_empty() {
+                return Err(e);
             }
-        })?;
+            warn!(
+                "keycloak: failed to refresh stale jwks, keeping cached keys: {}",
+                e
+            );
+        }
+
+        if let Some(key) = self.read_jwks()?.keys.get(kid) {
+            return Ok(key.clone());
+        }
+
+        self.refresh_jwks().await?;
+
+        self.read_jwks()?
+            .keys
+            .get(kid)
+            .cloned()
+            .ok_or_else(|| AuthError::KeyNotFound {
+                key: kid.to_string(),
+            })
+    }
+}
+
+impl AuthRepository for KeycloakAuthRepository {
+    async fn validate_token(
+        &self,
+        token: &str,
+    ) -> Result<crate::domain::models::claims::Claims, AuthError> {
+        #[cfg(feature = "failure-injection")]
+        self.failure_injector.check()?;
+
+        let claims = match self.validation_strategy {
+            ValidationStrategy::Jwt => self.validate_jwt(token).await?,
+            ValidationStrategy::Introspection => self.introspect(token).await?,
+        };
 
-        let claims = data.claims;
+        if let Some(expected) = &self.audience
+            && !claims
+                .aud
+                .as_ref()
+                .is_some_and(|aud| aud.contains(expected))
+        {
+            return Err(AuthError::InvalidAudience {
+                expected: expected.clone(),
+            });
+        }
 
+        // Tokens without an expiry would be valid forever: reject them, as
+        // JWT validation already does.
+        let exp = claims.exp.ok_or_else(|| AuthError::InvalidToken {
+            message: "missing exp claim".to_string(),
+        })?;
         let now = Utc::now().timestamp();
+        let leeway = i64::try_from(self.leeway.as_secs()).unwrap_or(i64::MAX);
 
-        if claims.exp.unwrap_or(0) < now {
+        if exp.saturating_add(leeway) < now {
             return Err(AuthError::Expired);
         }
 
@@ -119,8 +566,371 @@ impl AuthRepository for KeycloakAuthRepository {
         &self,
         token: &str,
     ) -> Result<crate::domain::models::identity::Identity, AuthError> {
+        // Checked before the cache lookup, so injected failures also apply
+        // to tokens whose identity is already cached.
+        #[cfg(feature = "failure-injection")]
+        self.failure_injector.check()?;
+
+        // Introspection exists to notice revoked tokens: a cached identity
+        // would keep accepting them until they expire.
+        let use_cache = self.validation_strategy == ValidationStrategy::Jwt;
+        let now = Utc::now().timestamp();
+
+        if use_cache && let Some(identity) = self.identities.get(token, now) {
+            return Ok(identity);
+        }
+
         let claims = self.validate_token(token).await?;
+        let expires_at = claims.exp.unwrap_or(0);
+        let identity = Identity::from(claims);
+
+        if use_cache {
+            self.identities.insert(token, identity.clone(), expires_at);
+        }
+
+        Ok(identity)
+    }
+
+    /// Identify a batch of tokens, e.g. a burst of reconnecting clients.
+    ///
+    /// Identical tokens are identified once, and at most
+    /// [`IDENTIFY_MANY_CONCURRENCY`] tokens are validated at the same time.
+    async fn identify_many(&self, tokens: &[&str]) -> Vec<Result<Identity, AuthError>> {
+        let mut unique: Vec<&str> = Vec::new();
+        let mut indices: HashMap<&str, usize> = HashMap::new();
+        let positions: Vec<usize> = tokens
+            .iter()
+            .map(|token| {
+                *indices.entry(token).or_insert_with(|| {
+                    unique.push(token);
+                    unique.len() - 1
+                })
+            })
+            .collect();
+
+        // Collected beforehand: a lazy `map` closure held across the await
+        // prevents the compiler from proving the returned future `Send`.
+        let lookups: Vec<_> = unique
+            .into_iter()
+            .map(|token| self.identify(token))
+            .collect();
+        let results: Vec<Result<Identity, AuthError>> = stream::iter(lookups)
+            .buffered(IDENTIFY_MANY_CONCURRENCY)
+            .collect()
+            .await;
+
+        positions.into_iter().map(|i| results[i].clone()).collect()
+    }
+
+    fn leeway(&self) -> Duration {
+        self.leeway
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use futures_util::future::join_all;
+    use serde_json::json;
+
+    use crate::domain::{
+        models::{client::Client, errors::AuthError, identity::Identity},
+        ports::AuthRepository,
+    };
+    use crate::infrastructure::keycloak_repository::{
+        JWKS_MAX_AGE, JWKS_MIN_REFRESH_INTERVAL, KeycloakAuthRepository, ValidationStrategy,
+    };
+    use crate::infrastructure::keycloak_stub::KeycloakStub;
+
+    fn identity(id: &str) -> Identity {
+        Identity::Client(Client {
+            id: id.to_string(),
+            client_id: "beep-bot".to_string(),
+            roles: Vec::new(),
+            scopes: Vec::new(),
+            expires_at: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_identify_many_keeps_input_order() {
+        let repository = KeycloakAuthRepository::new("http://127.0.0.1:1/realms/beep", None);
+        let expires_at = Utc::now().timestamp() + 60;
+        repository
+            .identities
+            .insert("token-a", identity("a"), expires_at);
+        repository
+            .identities
+            .insert("token-b", identity("b"), expires_at);
+
+        let results = repository
+            .identify_many(&["token-b", "token-a", "invalid", "token-b"])
+            .await;
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().ok(), Some(&identity("b")));
+        assert_eq!(results[1].as_ref().ok(), Some(&identity("a")));
+        assert!(matches!(results[2], Err(AuthError::InvalidToken { .. })));
+        assert_eq!(results[3].as_ref().ok(), Some(&identity("b")));
+    }
+
+    #[cfg(feature = "failure-injection")]
+    #[tokio::test]
+    async fn test_injected_failure_applies_to_cached_identity() {
+        let repository = KeycloakAuthRepository::new("http://127.0.0.1:1/realms/beep", None);
+        repository
+            .identities
+            .insert("token-a", identity("a"), Utc::now().timestamp() + 60);
+
+        repository.failure_injector.fail_next(AuthError::Expired, 1);
+
+        assert!(matches!(
+            repository.identify("token-a").await,
+            Err(AuthError::Expired)
+        ));
+        assert!(repository.identify("token-a").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_introspection_bypasses_identity_cache() {
+        let stub = KeycloakStub::start().await;
+        let repository = KeycloakAuthRepository::new(stub.issuer.clone(), None)
+            .with_client_credentials("client", "secret")
+            .with_validation_strategy(ValidationStrategy::Introspection);
+
+        let identity = repository.identify("opaque-token").await.unwrap();
+        assert_eq!(identity.id(), "opaque-token");
+
+        stub.set_introspection_active(false);
+
+        assert!(matches!(
+            repository.identify("opaque-token").await,
+            Err(AuthError::InvalidToken { .. })
+        ));
+        assert_eq!(KeycloakStub::count(&stub.calls.introspection), 2);
+    }
+
+    /// Pretend the cached JWKS was fetched `age` ago.
+    fn age_jwks(repository: &KeycloakAuthRepository, age: std::time::Duration) {
+        let mut cache = repository.jwks.write().unwrap();
+        cache.fetched_at = std::time::Instant::now().checked_sub(age);
+        cache.attempted_at = cache.fetched_at;
+    }
+
+    #[tokio::test]
+    async fn test_jwks_cache_hit() {
+        let stub = KeycloakStub::start().await;
+        let repository =
+            KeycloakAuthRepository::new(stub.issuer.clone(), None).with_identity_cache_capacity(0);
+
+        for _ in 0..3 {
+            let claims = repository
+                .validate_token(&stub.sign("key-1", "user-1"))
+                .await
+                .unwrap();
+            assert_eq!(claims.sub.0, "user-1");
+        }
+
+        assert_eq!(KeycloakStub::count(&stub.calls.jwks), 1);
+    }
+
+    #[tokio::test]
+    async fn test_jwks_refetch_on_unknown_kid() {
+        let stub = KeycloakStub::start().await;
+        let repository = KeycloakAuthRepository::new(stub.issuer.clone(), None);
+
+        repository
+            .validate_token(&stub.sign("key-1", "user-1"))
+            .await
+            .unwrap();
+
+        stub.publish_kids(&["key-1", "key-2"]);
+        age_jwks(&repository, JWKS_MIN_REFRESH_INTERVAL);
+
+        let claims = repository
+            .validate_token(&stub.sign("key-2", "user-2"))
+            .await
+            .unwrap();
+
+        assert_eq!(claims.sub.0, "user-2");
+        assert_eq!(KeycloakStub::count(&stub.calls.jwks), 2);
+    }
+
+    #[tokio::test]
+    async fn test_jwks_refetch_is_throttled() {
+        let stub = KeycloakStub::start().await;
+        let repository = KeycloakAuthRepository::new(stub.issuer.clone(), None);
+
+        for _ in 0..3 {
+            let result = repository
+                .validate_token(&stub.sign("unknown", "user-1"))
+                .await;
+            assert!(matches!(result, Err(AuthError::KeyNotFound { .. })));
+        }
+
+        assert_eq!(KeycloakStub::count(&stub.calls.jwks), 1);
+    }
+
+    #[tokio::test]
+    async fn test_jwks_drops_rotated_out_keys_after_max_age() {
+        let stub = KeycloakStub::start().await;
+        let repository =
+            KeycloakAuthRepository::new(stub.issuer.clone(), None).with_identity_cache_capacity(0);
+
+        repository
+            .validate_token(&stub.sign("key-1", "user-1"))
+            .await
+            .unwrap();
+
+        stub.publish_kids(&["key-2"]);
+        age_jwks(&repository, JWKS_MAX_AGE);
+
+        let result = repository
+            .validate_token(&stub.sign("key-1", "user-1"))
+            .await;
+
+        assert!(matches!(result, Err(AuthError::KeyNotFound { .. })));
+        assert_eq!(KeycloakStub::count(&stub.calls.jwks), 2);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_misses_share_one_jwks_fetch() {
+        let stub = KeycloakStub::start().await;
+        let repository = KeycloakAuthRepository::new(stub.issuer.clone(), None);
+        let token = stub.sign("key-1", "user-1");
+
+        let results = join_all((0..5).map(|_| repository.validate_token(&token))).await;
+
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(KeycloakStub::count(&stub.calls.jwks), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_jwks_fetch_is_throttled() {
+        let stub = KeycloakStub::start().await;
+        stub.set_jwks_available(false);
+        let repository = KeycloakAuthRepository::new(stub.issuer.clone(), None);
+
+        for _ in 0..3 {
+            let result = repository
+                .validate_token(&stub.sign("key-1", "user-1"))
+                .await;
+            assert!(matches!(result, Err(AuthError::Network { .. })));
+        }
+
+        assert_eq!(KeycloakStub::count(&stub.calls.jwks), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stale_jwks_kept_while_issuer_down() {
+        let stub = KeycloakStub::start().await;
+        let repository =
+            KeycloakAuthRepository::new(stub.issuer.clone(), None).with_identity_cache_capacity(0);
+
+        repository
+            .validate_token(&stub.sign("key-1", "user-1"))
+            .await
+            .unwrap();
+
+        stub.set_jwks_available(false);
+        age_jwks(&repository, JWKS_MAX_AGE);
+
+        for _ in 0..3 {
+            repository
+                .validate_token(&stub.sign("key-1", "user-1"))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(KeycloakStub::count(&stub.calls.jwks), 2);
+    }
+
+    #[tokio::test]
+    async fn test_audience_accepted() {
+        let stub = KeycloakStub::start().await;
+        let repository =
+            KeycloakAuthRepository::new(stub.issuer.clone(), Some("beep-api".to_string()));
+
+        for aud in [json!("beep-api"), json!(["account", "beep-api"])] {
+            let token = stub.sign_with("key-1", "user-1", json!({ "aud": aud }));
+
+            assert!(repository.validate_token(&token).await.is_ok(), "{aud}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audience_mismatch_rejected() {
+        let stub = KeycloakStub::start().await;
+        let repository =
+            KeycloakAuthRepository::new(stub.issuer.clone(), Some("beep-api".to_string()));
+
+        for token in [
+            stub.sign_with("key-1", "user-1", json!({ "aud": "account" })),
+            stub.sign("key-1", "user-1"),
+        ] {
+            let result = repository.validate_token(&token).await;
+
+            assert!(matches!(
+                result,
+                Err(AuthError::InvalidAudience { expected }) if expected == "beep-api"
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audience_check_skipped_without_audience() {
+        let stub = KeycloakStub::start().await;
+        let repository = KeycloakAuthRepository::new(stub.issuer.clone(), None);
+        let token = stub.sign_with("key-1", "user-1", json!({ "aud": "account" }));
+
+        assert!(repository.validate_token(&token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_token_without_expiry_rejected() {
+        let stub = KeycloakStub::start().await;
+        stub.set_introspection_expiry(false);
+        let jwt_repository = KeycloakAuthRepository::new(stub.issuer.clone(), None);
+        let introspection_repository = KeycloakAuthRepository::new(stub.issuer.clone(), None)
+            .with_client_credentials("client", "secret")
+            .with_validation_strategy(ValidationStrategy::Introspection);
+
+        let jwt = stub.sign_with("key-1", "user-1", json!({ "exp": null }));
+
+        assert!(matches!(
+            jwt_repository.validate_token(&jwt).await,
+            Err(AuthError::InvalidToken { .. })
+        ));
+        assert!(matches!(
+            introspection_repository.validate_token("opaque-token").await,
+            Err(AuthError::InvalidToken { message }) if message == "missing exp claim"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_service_token_reused_before_expiry() {
+        let stub = KeycloakStub::start().await;
+        let repository = KeycloakAuthRepository::new(stub.issuer.clone(), None)
+            .with_client_credentials("client", "secret");
+
+        let first = repository.service_token().await.unwrap();
+        let second = repository.service_token().await.unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(KeycloakStub::count(&stub.calls.token), 1);
+    }
+
+    #[tokio::test]
+    async fn test_service_token_refreshed_near_expiry() {
+        let stub = KeycloakStub::start().await;
+        stub.set_token_expires_in(10);
+        let repository = KeycloakAuthRepository::new(stub.issuer.clone(), None)
+            .with_client_credentials("client", "secret");
+
+        let first = repository.service_token().await.unwrap();
+        let second = repository.service_token().await.unwrap();
 
-        Ok(Identity::from(claims))
+        assert_ne!(first, second);
+        assert_eq!(KeycloakStub::count(&stub.calls.token), 2);
     }
 }
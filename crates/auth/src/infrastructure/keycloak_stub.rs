@@ -0,0 +1,195 @@
+//! Local stand-in for a Keycloak realm, used by the repository tests.
+
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use axum::{Form, Json, Router, extract::State, http::StatusCode, routing::get, routing::post};
+use chrono::Utc;
+use jsonwebtoken::{Algorithm, EncodingKey, Header, encode};
+use serde_json::{Value, json};
+
+const SIGNING_KEY: &str = include_str!("keycloak_stub_key.pem");
+const MODULUS: &str = "xp_Az-TPTRm0paYKi0Mi5iljgPwnmHptefQvRfLZbma9v3MW0kx89xfn8jBUoEHxQFV6h3iosOnD8RF7-BRwJ9JcQiGSdO53wTn-sE21XeR6PSxI6IR87nFLIR4JHplLaU74IK-o1ACqcBD8K0eRryzX64FdUch9vGOT2RxjdpmSevnIdI50bsXUk48IoW0T-mnqVCtiHJNsAoB3J9rbHxPVxoE12o3xoVz-uFjIWG80rmGQjoGSjTGX70ZlkBWjZVBg_PjfIkCLvcnZDA32Wg35oTZPwSlvmoxIyQ_BCTmD3qWpSsAU3VW2tRXQyDVi1nfwanwoMVjf-g-vuxUK9w";
+const EXPONENT: &str = "AQAB";
+
+#[derive(Default)]
+pub(crate) struct StubCalls {
+    pub(crate) jwks: AtomicUsize,
+    pub(crate) token: AtomicUsize,
+    pub(crate) introspection: AtomicUsize,
+}
+
+struct StubState {
+    issuer: String,
+    /// Key ids published in the JWKS, all backed by the same key pair.
+    kids: Mutex<Vec<String>>,
+    jwks_available: Mutex<bool>,
+    introspection_active: Mutex<bool>,
+    /// Whether introspection responses carry an `exp` claim.
+    introspection_expiry: Mutex<bool>,
+    token_expires_in: Mutex<u64>,
+    calls: Arc<StubCalls>,
+}
+
+/// Realm served on a random local port.
+pub(crate) struct KeycloakStub {
+    pub(crate) issuer: String,
+    pub(crate) calls: Arc<StubCalls>,
+    state: Arc<StubState>,
+}
+
+impl KeycloakStub {
+    pub(crate) async fn start() -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}/realms/beep", listener.local_addr().unwrap());
+        let calls = Arc::new(StubCalls::default());
+        let state = Arc::new(StubState {
+            issuer: issuer.clone(),
+            kids: Mutex::new(vec!["key-1".to_string()]),
+            jwks_available: Mutex::new(true),
+            introspection_active: Mutex::new(true),
+            introspection_expiry: Mutex::new(true),
+            token_expires_in: Mutex::new(300),
+            calls: calls.clone(),
+        });
+
+        let router = Router::new()
+            .route(
+                "/realms/beep/.well-known/openid-configuration",
+                get(openid_configuration),
+            )
+            .route("/realms/beep/certs", get(certs))
+            .route("/realms/beep/token", post(token))
+            .route("/realms/beep/introspect", post(introspect))
+            .with_state(state.clone());
+
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        Self {
+            issuer,
+            calls,
+            state,
+        }
+    }
+
+    /// Replace the key ids published in the JWKS.
+    pub(crate) fn publish_kids(&self, kids: &[&str]) {
+        *self.state.kids.lock().unwrap() = kids.iter().map(|kid| kid.to_string()).collect();
+    }
+
+    /// Answer JWKS requests with `503 Service Unavailable` while `false`.
+    pub(crate) fn set_jwks_available(&self, available: bool) {
+        *self.state.jwks_available.lock().unwrap() = available;
+    }
+
+    pub(crate) fn set_introspection_active(&self, active: bool) {
+        *self.state.introspection_active.lock().unwrap() = active;
+    }
+
+    pub(crate) fn set_introspection_expiry(&self, expiry: bool) {
+        *self.state.introspection_expiry.lock().unwrap() = expiry;
+    }
+
+    pub(crate) fn set_token_expires_in(&self, expires_in: u64) {
+        *self.state.token_expires_in.lock().unwrap() = expires_in;
+    }
+
+    /// RS256 JWT issued by the realm and signed with key `kid`.
+    pub(crate) fn sign(&self, kid: &str, sub: &str) -> String {
+        self.sign_with(kid, sub, json!({}))
+    }
+
+    /// Same as [`Self::sign`], adding or overriding the `extra` claims.
+    pub(crate) fn sign_with(&self, kid: &str, sub: &str, extra: Value) -> String {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(kid.to_string());
+
+        let mut claims = json!({
+            "sub": sub,
+            "iss": self.issuer,
+            "exp": Utc::now().timestamp() + 300,
+            "preferred_username": sub,
+        });
+        if let (Some(claims), Value::Object(extra)) = (claims.as_object_mut(), extra) {
+            claims.extend(extra);
+        }
+
+        encode(
+            &header,
+            &claims,
+            &EncodingKey::from_rsa_pem(SIGNING_KEY.as_bytes()).unwrap(),
+        )
+        .unwrap()
+    }
+
+    pub(crate) fn count(counter: &AtomicUsize) -> usize {
+        counter.load(Ordering::SeqCst)
+    }
+}
+
+async fn openid_configuration(State(state): State<Arc<StubState>>) -> Json<Value> {
+    Json(json!({
+        "jwks_uri": format!("{}/certs", state.issuer),
+        "token_endpoint": format!("{}/token", state.issuer),
+        "introspection_endpoint": format!("{}/introspect", state.issuer),
+    }))
+}
+
+async fn certs(State(state): State<Arc<StubState>>) -> Result<Json<Value>, StatusCode> {
+    state.calls.jwks.fetch_add(1, Ordering::SeqCst);
+
+    if !*state.jwks_available.lock().unwrap() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    let keys: Vec<Value> = state
+        .kids
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|kid| json!({ "kid": kid, "n": MODULUS, "e": EXPONENT }))
+        .collect();
+
+    Ok(Json(json!({ "keys": keys })))
+}
+
+async fn token(State(state): State<Arc<StubState>>) -> Json<Value> {
+    let call = state.calls.token.fetch_add(1, Ordering::SeqCst);
+
+    Json(json!({
+        "access_token": format!("service-token-{call}"),
+        "expires_in": *state.token_expires_in.lock().unwrap(),
+    }))
+}
+
+async fn introspect(
+    State(state): State<Arc<StubState>>,
+    Form(form): Form<Vec<(String, String)>>,
+) -> Json<Value> {
+    state.calls.introspection.fetch_add(1, Ordering::SeqCst);
+
+    if !*state.introspection_active.lock().unwrap() {
+        return Json(json!({ "active": false }));
+    }
+
+    let sub = form
+        .iter()
+        .find(|(key, _)| key == "token")
+        .map(|(_, token)| token.clone())
+        .unwrap_or_default();
+
+    let exp = state
+        .introspection_expiry
+        .lock()
+        .unwrap()
+        .then(|| Utc::now().timestamp() + 300);
+
+    Json(json!({
+        "active": true,
+        "sub": sub,
+        "iss": state.issuer,
+        "exp": exp,
+    }))
+}
@@ -1 +1,6 @@
+#[cfg(feature = "failure-injection")]
+pub mod failure_injection;
+pub(crate) mod identity_cache;
 pub mod keycloak_repository;
+#[cfg(test)]
+pub(crate) mod keycloak_stub;
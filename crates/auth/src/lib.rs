@@ -2,7 +2,13 @@ mod application;
 pub(crate) mod domain;
 pub(crate) mod infrastructure;
 
-pub use infrastructure::keycloak_repository::KeycloakAuthRepository;
+pub use infrastructure::identity_cache::DEFAULT_IDENTITY_CACHE_CAPACITY;
+pub use infrastructure::keycloak_repository::{
+    DEFAULT_TOKEN_LEEWAY, KeycloakAuthRepository, ValidationStrategy,
+};
+
+#[cfg(feature = "failure-injection")]
+pub use infrastructure::failure_injection::FailureInjector;
 
 pub use domain::models::*;
 pub use domain::ports::*;
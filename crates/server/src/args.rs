@@ -1,3 +1,16 @@
+use std::{path::PathBuf, time::Duration};
+
+use tower_http::compression::CompressionLayer;
+
+use crate::{
+    config::TlsConfig,
+    http::{
+        body_limit::{BodyLimit, DEFAULT_BODY_LIMIT},
+        compression::{CompressionPredicate, DEFAULT_COMPRESSION_MIN_SIZE, compression_layer},
+        timeout::RequestTimeout,
+    },
+};
+
 pub mod auth;
 pub mod log;
 
@@ -9,7 +22,7 @@ pub struct ServerArgs {
         env,
         num_args = 0..,
         value_delimiter = ',',
-        long_help = "The port to run the application on",
+        long_help = "The origins allowed by CORS, or `*` to allow any origin",
     )]
     pub allowed_origins: Vec<String>,
     #[arg(
@@ -30,6 +43,90 @@ pub struct ServerArgs {
         long_help = "The port to run the application on"
     )]
     pub port: u16,
+    #[arg(
+        long = "server-shutdown-grace-period",
+        env = "SERVER_SHUTDOWN_GRACE_PERIOD",
+        name = "SERVER_SHUTDOWN_GRACE_PERIOD",
+        default_value_t = 30,
+        long_help = "How long in-flight requests are given to complete on shutdown, in seconds"
+    )]
+    pub shutdown_grace_period: u64,
+    #[arg(
+        long = "server-request-timeout",
+        env = "SERVER_REQUEST_TIMEOUT",
+        name = "SERVER_REQUEST_TIMEOUT",
+        default_value_t = 30,
+        long_help = "How long a request may run before it is answered with 504, in seconds"
+    )]
+    pub request_timeout: u64,
+    #[arg(
+        long = "server-body-limit",
+        env = "SERVER_BODY_LIMIT",
+        name = "SERVER_BODY_LIMIT",
+        default_value_t = DEFAULT_BODY_LIMIT,
+        long_help = "Maximum size of a request body, in bytes (2 MiB by default)"
+    )]
+    pub body_limit: usize,
+    #[arg(
+        long = "server-compression",
+        env = "SERVER_COMPRESSION",
+        name = "SERVER_COMPRESSION",
+        default_value_t = false,
+        long_help = "Compress responses with gzip or brotli when the client accepts it"
+    )]
+    pub compression: bool,
+    #[arg(
+        long = "server-compression-min-size",
+        env = "SERVER_COMPRESSION_MIN_SIZE",
+        name = "SERVER_COMPRESSION_MIN_SIZE",
+        default_value_t = DEFAULT_COMPRESSION_MIN_SIZE,
+        long_help = "Minimum size of a compressed response, in bytes"
+    )]
+    pub compression_min_size: u16,
+    #[arg(
+        long = "server-tls-cert",
+        env = "SERVER_TLS_CERT",
+        name = "SERVER_TLS_CERT",
+        requires = "SERVER_TLS_KEY",
+        long_help = "Path to the PEM certificate chain used to terminate TLS"
+    )]
+    pub tls_cert: Option<PathBuf>,
+    #[arg(
+        long = "server-tls-key",
+        env = "SERVER_TLS_KEY",
+        name = "SERVER_TLS_KEY",
+        requires = "SERVER_TLS_CERT",
+        long_help = "Path to the PEM private key used to terminate TLS"
+    )]
+    pub tls_key: Option<PathBuf>,
+}
+
+impl ServerArgs {
+    pub fn shutdown_grace_period(&self) -> Duration {
+        Duration::from_secs(self.shutdown_grace_period)
+    }
+
+    pub fn request_timeout(&self) -> RequestTimeout {
+        RequestTimeout(Duration::from_secs(self.request_timeout))
+    }
+
+    pub fn body_limit(&self) -> BodyLimit {
+        BodyLimit(self.body_limit)
+    }
+
+    /// Response compression layer, when compression is enabled.
+    pub fn compression_layer(&self) -> Option<CompressionLayer<CompressionPredicate>> {
+        self.compression
+            .then(|| compression_layer(self.compression_min_size))
+    }
+
+    /// TLS configuration, when both a certificate and a key are set.
+    pub fn tls_config(&self) -> Option<TlsConfig> {
+        Some(TlsConfig {
+            cert_path: self.tls_cert.clone()?,
+            key_path: self.tls_key.clone()?,
+        })
+    }
 }
 
 impl Default for ServerArgs {
@@ -38,6 +135,13 @@ impl Default for ServerArgs {
             allowed_origins: vec![],
             host: "0.0.0.0".into(),
             port: 3333,
+            shutdown_grace_period: 30,
+            request_timeout: 30,
+            body_limit: DEFAULT_BODY_LIMIT,
+            compression: false,
+            compression_min_size: DEFAULT_COMPRESSION_MIN_SIZE,
+            tls_cert: None,
+            tls_key: None,
         }
     }
 }
@@ -1,3 +1,27 @@
+use std::time::Duration;
+
+use beep_auth::{DEFAULT_TOKEN_LEEWAY, KeycloakAuthRepository, ValidationStrategy};
+
+/// How access tokens are validated, see [`ValidationStrategy`].
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AuthValidationStrategy {
+    /// Verify JWTs locally against the realm's JWKS.
+    #[default]
+    Jwt,
+    /// Ask Keycloak's introspection endpoint on every request, so revoked
+    /// tokens are rejected immediately. Requires client credentials.
+    Introspection,
+}
+
+impl From<AuthValidationStrategy> for ValidationStrategy {
+    fn from(strategy: AuthValidationStrategy) -> Self {
+        match strategy {
+            AuthValidationStrategy::Jwt => ValidationStrategy::Jwt,
+            AuthValidationStrategy::Introspection => ValidationStrategy::Introspection,
+        }
+    }
+}
+
 #[derive(clap::Args, Debug, Clone)]
 pub struct AuthArgs {
     #[arg(
@@ -26,6 +50,59 @@ pub struct AuthArgs {
         long_help = "The client secret of service account"
     )]
     pub client_secret: String,
+
+    #[arg(
+        long = "auth-validate-audience",
+        env = "AUTH_VALIDATE_AUDIENCE",
+        name = "AUTH_VALIDATE_AUDIENCE",
+        default_value_t = true,
+        action = clap::ArgAction::Set,
+        long_help = "Whether tokens must be issued for the client id of the service account"
+    )]
+    pub validate_audience: bool,
+
+    #[arg(
+        long = "auth-token-leeway",
+        env = "AUTH_TOKEN_LEEWAY",
+        name = "AUTH_TOKEN_LEEWAY",
+        default_value_t = DEFAULT_TOKEN_LEEWAY.as_secs(),
+        long_help = "Clock skew tolerated when checking token expiry, in seconds"
+    )]
+    pub token_leeway: u64,
+
+    #[arg(
+        long = "auth-validation-strategy",
+        env = "AUTH_VALIDATION_STRATEGY",
+        name = "AUTH_VALIDATION_STRATEGY",
+        value_enum,
+        default_value_t = AuthValidationStrategy::Jwt,
+        long_help = "How access tokens are validated: locally against the JWKS, or through the introspection endpoint"
+    )]
+    pub validation_strategy: AuthValidationStrategy,
+
+    #[arg(
+        long = "auth-cookie-name",
+        env = "AUTH_COOKIE_NAME",
+        name = "AUTH_COOKIE_NAME",
+        long_help = "The cookie holding the access token when a request has no Authorization header; cookie authentication is disabled when unset"
+    )]
+    pub cookie_name: Option<String>,
+}
+
+impl AuthArgs {
+    /// Audience expected in tokens, if the check is enabled.
+    pub fn expected_audience(&self) -> Option<String> {
+        self.validate_audience.then(|| self.client_id.clone())
+    }
+}
+
+impl From<&AuthArgs> for KeycloakAuthRepository {
+    fn from(args: &AuthArgs) -> Self {
+        KeycloakAuthRepository::new(args.issuer.clone(), args.expected_audience())
+            .with_client_credentials(args.client_id.clone(), args.client_secret.clone())
+            .with_leeway(Duration::from_secs(args.token_leeway))
+            .with_validation_strategy(args.validation_strategy.into())
+    }
 }
 
 impl Default for AuthArgs {
@@ -34,6 +111,10 @@ impl Default for AuthArgs {
             issuer: "http://localhost:8080/realms/beep".to_string(),
             client_id: "client_id".to_string(),
             client_secret: "client_secret".to_string(),
+            validate_audience: true,
+            token_leeway: DEFAULT_TOKEN_LEEWAY.as_secs(),
+            validation_strategy: AuthValidationStrategy::default(),
+            cookie_name: None,
         }
     }
 }
@@ -1,5 +1,14 @@
+use std::path::PathBuf;
+
 pub struct AuthConfig {
     pub issuer: String,
     pub client_id: String,
     pub client_secret: String,
 }
+
+/// PEM files used to terminate TLS in the server.
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
@@ -0,0 +1,183 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{StatusCode, header::CONTENT_LENGTH},
+    middleware::Next,
+    response::Response,
+};
+use http_body_util::{BodyExt, LengthLimitError, Limited};
+use tracing::warn;
+
+use crate::ApiError;
+
+/// Default maximum request body size: 2 MiB.
+pub const DEFAULT_BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Maximum request body size in bytes, used as the state of
+/// [`body_limit_middleware`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyLimit(pub usize);
+
+impl Default for BodyLimit {
+    fn default() -> Self {
+        Self(DEFAULT_BODY_LIMIT)
+    }
+}
+
+/// Reject request bodies larger than [`BodyLimit`] with
+/// `413 Payload Too Large` (`E_PAYLOAD_TOO_LARGE`).
+///
+/// Requests announcing a larger `Content-Length` are rejected before any of
+/// the body is read. Other bodies are capped while streaming, so extractors
+/// fail as soon as the limit is crossed instead of buffering everything.
+///
+/// axum extractors also enforce their own 2 MiB limit: when configuring a
+/// larger one, raise it with `axum::extract::DefaultBodyLimit::max`.
+pub async fn body_limit_middleware(
+    State(BodyLimit(limit)): State<BodyLimit>,
+    req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let content_length = req
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+
+    if content_length.is_some_and(|length| length > limit as u64) {
+        warn!("body limit middleware: rejected body larger than {limit} bytes");
+        return Err(ApiError::PayloadTooLarge { limit });
+    }
+
+    // Flag the body crossing the limit, so only the 413 answered by
+    // extractors to that error is turned into the structured one.
+    let exceeded = Arc::new(AtomicBool::new(false));
+    let req = req.map(|body| {
+        let exceeded = exceeded.clone();
+        Body::new(Limited::new(body, limit).map_err(move |error| {
+            if error.is::<LengthLimitError>() {
+                exceeded.store(true, Ordering::Relaxed);
+            }
+            error
+        }))
+    });
+    let response = next.run(req).await;
+
+    if exceeded.load(Ordering::Relaxed) && response.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        warn!("body limit middleware: body crossed the {limit} bytes limit");
+        return Err(ApiError::PayloadTooLarge { limit });
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+    };
+
+    use axum::{
+        Json, Router,
+        body::{Body, Bytes},
+        http::{Request, StatusCode, header::CONTENT_LENGTH, header::CONTENT_TYPE},
+        middleware::from_fn_with_state,
+        routing::post,
+    };
+    use futures_util::{StreamExt, stream};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::http::body_limit::{BodyLimit, body_limit_middleware};
+
+    const LIMIT: usize = 1024;
+    const CHUNK: usize = 256;
+
+    fn router() -> Router {
+        Router::new()
+            .route(
+                "/",
+                post(|Json(_): Json<serde::de::IgnoredAny>| async { "ok" }),
+            )
+            .route(
+                "/teapot",
+                post(|| async { (StatusCode::PAYLOAD_TOO_LARGE, "handler says no") }),
+            )
+            .layer(from_fn_with_state(BodyLimit(LIMIT), body_limit_middleware))
+    }
+
+    async fn send(request: Request<Body>) -> (StatusCode, String) {
+        let response = router().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_content_length_checked_before_reading() {
+        let polled = Arc::new(AtomicUsize::new(0));
+        let body = Body::from_stream(stream::once({
+            let polled = polled.clone();
+            async move {
+                polled.fetch_add(1, Ordering::SeqCst);
+                Ok::<_, Infallible>(Bytes::from_static(b"{}"))
+            }
+        }));
+
+        let (status, body) = send(
+            Request::post("/")
+                .header(CONTENT_TYPE, "application/json")
+                .header(CONTENT_LENGTH, LIMIT + 1)
+                .body(body)
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(body.contains("E_PAYLOAD_TOO_LARGE"));
+        assert_eq!(polled.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_body_rejected_when_crossing_limit() {
+        let sent = Arc::new(AtomicUsize::new(0));
+        let chunks = stream::repeat_with({
+            let sent = sent.clone();
+            move || {
+                sent.fetch_add(CHUNK, Ordering::SeqCst);
+                Ok::<_, Infallible>(Bytes::from(vec![b' '; CHUNK]))
+            }
+        })
+        .take(1024);
+
+        let (status, body) = send(
+            Request::post("/")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from_stream(chunks))
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(body.contains("E_PAYLOAD_TOO_LARGE"));
+        assert!(sent.load(Ordering::SeqCst) <= LIMIT + CHUNK);
+    }
+
+    #[tokio::test]
+    async fn test_handler_413_left_untouched() {
+        let (status, body) = send(Request::post("/teapot").body(Body::empty()).unwrap()).await;
+
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body, "handler says no");
+    }
+}
@@ -0,0 +1,104 @@
+use tower_http::compression::{
+    CompressionLayer,
+    predicate::{And, NotForContentType, Predicate, SizeAbove},
+};
+
+/// Default minimum response size compressed, in bytes. Smaller bodies
+/// usually grow once the gzip/br framing is added.
+pub const DEFAULT_COMPRESSION_MIN_SIZE: u16 = 1024;
+
+/// Responses compressed by [`compression_layer`]: at least the minimum
+/// size, and neither gRPC, images nor server-sent events.
+pub type CompressionPredicate =
+    And<And<And<SizeAbove, NotForContentType>, NotForContentType>, NotForContentType>;
+
+/// Build a layer compressing responses with gzip or brotli, as negotiated
+/// through the request's `Accept-Encoding`.
+///
+/// Responses smaller than `min_size` bytes are sent as is. Apply it as the
+/// outermost layer so error responses of the other middlewares, e.g. the
+/// auth middleware, are compressed too.
+pub fn compression_layer(min_size: u16) -> CompressionLayer<CompressionPredicate> {
+    let predicate = SizeAbove::new(min_size)
+        .and(NotForContentType::GRPC)
+        .and(NotForContentType::IMAGES)
+        .and(NotForContentType::SSE);
+
+    CompressionLayer::new()
+        .gzip(true)
+        .br(true)
+        .compress_when(predicate)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{
+            Request, StatusCode,
+            header::{ACCEPT_ENCODING, CONTENT_ENCODING},
+        },
+        response::Response,
+        routing::get,
+    };
+    use tower::ServiceExt;
+
+    use crate::{ApiError, http::compression::compression_layer};
+
+    fn router() -> Router {
+        Router::new()
+            .route("/large", get(|| async { "a".repeat(4096) }))
+            .route("/small", get(|| async { "a" }))
+            .route(
+                "/error",
+                get(|| async {
+                    Err::<(), _>(ApiError::InvalidToken {
+                        message: "a".repeat(4096),
+                    })
+                }),
+            )
+            .layer(compression_layer(1024))
+    }
+
+    async fn call(uri: &str, accept_encoding: Option<&str>) -> Response {
+        let mut request = Request::get(uri);
+        if let Some(accept_encoding) = accept_encoding {
+            request = request.header(ACCEPT_ENCODING, accept_encoding);
+        }
+
+        router()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_compresses_large_responses() {
+        let response = call("/large", Some("gzip")).await;
+
+        assert_eq!(response.headers()[CONTENT_ENCODING], "gzip");
+    }
+
+    #[tokio::test]
+    async fn test_skips_small_responses() {
+        let response = call("/small", Some("gzip")).await;
+
+        assert!(response.headers().get(CONTENT_ENCODING).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_skips_clients_without_accept_encoding() {
+        let response = call("/large", None).await;
+
+        assert!(response.headers().get(CONTENT_ENCODING).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_compresses_error_responses() {
+        let response = call("/error", Some("br")).await;
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[CONTENT_ENCODING], "br");
+    }
+}
@@ -0,0 +1,132 @@
+use axum::http::{
+    HeaderValue, Method,
+    header::{AUTHORIZATION, CONTENT_TYPE},
+};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tracing::warn;
+
+const ALLOWED_METHODS: [Method; 6] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::PATCH,
+    Method::DELETE,
+    Method::OPTIONS,
+];
+
+/// Build a CORS layer allowing the given origins, the standard methods and
+/// the `Authorization` and `Content-Type` headers.
+///
+/// - An empty list yields no layer, leaving CORS disabled.
+/// - A literal `*` allows any origin. Browsers refuse credentialed requests
+///   against a wildcard origin, so do not combine it with
+///   `allow_credentials(true)`; list the origins explicitly instead.
+/// - Origins that are not valid header values are skipped with a warning.
+pub fn cors_layer(origins: &[String]) -> Option<CorsLayer> {
+    if origins.is_empty() {
+        return None;
+    }
+
+    let allow_origin = if origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::from(Any)
+    } else {
+        AllowOrigin::list(origins.iter().filter_map(|origin| {
+            HeaderValue::from_str(origin)
+                .inspect_err(|_| warn!("cors: ignoring invalid origin {origin}"))
+                .ok()
+        }))
+    };
+
+    Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods(ALLOWED_METHODS)
+            .allow_headers([AUTHORIZATION, CONTENT_TYPE]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{
+            Method, Request, StatusCode,
+            header::{
+                ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+                ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_REQUEST_HEADERS,
+                ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+            },
+        },
+        response::Response,
+        routing::get,
+    };
+    use tower::ServiceExt;
+
+    use crate::http::cors::cors_layer;
+
+    async fn preflight(origins: &[&str], origin: &str) -> Response {
+        let origins: Vec<String> = origins.iter().map(|origin| origin.to_string()).collect();
+        let mut router = Router::new().route("/", get(|| async { "ok" }));
+        if let Some(layer) = cors_layer(&origins) {
+            router = router.layer(layer);
+        }
+
+        router
+            .oneshot(
+                Request::builder()
+                    .method(Method::OPTIONS)
+                    .uri("/")
+                    .header(ORIGIN, origin)
+                    .header(ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                    .header(ACCESS_CONTROL_REQUEST_HEADERS, "authorization")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_no_origins_disables_cors() {
+        let response = preflight(&[], "https://app.beep.com").await;
+
+        assert!(cors_layer(&[]).is_none());
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert!(!response.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[tokio::test]
+    async fn test_wildcard_allows_any_origin() {
+        let response = preflight(&["*"], "https://anywhere.example").await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        assert!(
+            response.headers()[ACCESS_CONTROL_ALLOW_METHODS]
+                .to_str()
+                .unwrap()
+                .contains("POST")
+        );
+        assert!(
+            response.headers()[ACCESS_CONTROL_ALLOW_HEADERS]
+                .to_str()
+                .unwrap()
+                .contains("authorization")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_explicit_origins() {
+        let origins = ["https://app.beep.com", "https://admin.beep.com"];
+
+        let allowed = preflight(&origins, "https://admin.beep.com").await;
+        let denied = preflight(&origins, "https://evil.example").await;
+
+        assert_eq!(
+            allowed.headers()[ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://admin.beep.com"
+        );
+        assert!(!denied.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+}
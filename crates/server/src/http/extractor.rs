@@ -0,0 +1,142 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::{FromRequestParts, OptionalFromRequestParts},
+    http::request::Parts,
+};
+use beep_auth::Identity;
+
+use crate::ApiError;
+
+/// Identity of the caller, as inserted by `auth_middleware` or
+/// `optional_auth_middleware`.
+///
+/// Rejects with a `401` (`E_UNAUTHORIZED`) when the request was not
+/// authenticated. Behind `optional_auth_middleware`, extract
+/// `Option<AuthenticatedUser>` to handle anonymous requests instead.
+///
+/// ```rust,no_run
+/// # use beep_server::http::AuthenticatedUser;
+/// async fn me(AuthenticatedUser(identity): AuthenticatedUser) -> String {
+///     identity.id().to_string()
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthenticatedUser(pub Identity);
+
+impl<S> FromRequestParts<S> for AuthenticatedUser
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Identity>()
+            .cloned()
+            .map(AuthenticatedUser)
+            .ok_or(ApiError::TokenNotFound)
+    }
+}
+
+impl<S> OptionalFromRequestParts<S> for AuthenticatedUser
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<Identity>()
+            .cloned()
+            .map(AuthenticatedUser))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use beep_auth::{Client, Identity};
+    use tower::ServiceExt;
+
+    use crate::http::extractor::AuthenticatedUser;
+
+    fn identity() -> Identity {
+        Identity::Client(Client {
+            id: "client-id".to_string(),
+            client_id: "client".to_string(),
+            roles: vec![],
+            scopes: vec![],
+            expires_at: None,
+        })
+    }
+
+    fn router() -> Router {
+        Router::new()
+            .route(
+                "/required",
+                get(
+                    |AuthenticatedUser(identity): AuthenticatedUser| async move {
+                        identity.id().to_string()
+                    },
+                ),
+            )
+            .route(
+                "/optional",
+                get(|user: Option<AuthenticatedUser>| async move {
+                    user.map_or("anonymous".to_string(), |AuthenticatedUser(identity)| {
+                        identity.id().to_string()
+                    })
+                }),
+            )
+    }
+
+    async fn call(uri: &str, identity: Option<Identity>) -> (StatusCode, String) {
+        let mut request = Request::get(uri).body(Body::empty()).unwrap();
+        if let Some(identity) = identity {
+            request.extensions_mut().insert(identity);
+        }
+
+        let response = router().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_extracts_identity() {
+        let (status, body) = call("/required", Some(identity())).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "client-id");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_unauthenticated_request() {
+        let (status, body) = call("/required", None).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(body.contains("E_UNAUTHORIZED"));
+    }
+
+    #[tokio::test]
+    async fn test_optional_extractor_allows_anonymous_request() {
+        let (status, body) = call("/optional", None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "anonymous");
+    }
+}
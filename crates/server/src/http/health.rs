@@ -0,0 +1,212 @@
+use std::{future::Future, pin::Pin, sync::Arc, time::Duration};
+
+use axum::{Router, routing::get};
+use futures_util::future::join_all;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::{ApiError, http::response::Response};
+
+type ReadinessFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+type ReadinessCheck = Arc<dyn Fn() -> ReadinessFuture + Send + Sync>;
+
+/// Time a readiness check may take before it counts as failed.
+pub const DEFAULT_READINESS_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HealthStatus {
+    pub status: &'static str,
+}
+
+/// Router serving `GET /health`, and `GET /ready` once readiness checks are
+/// registered.
+///
+/// Neither route requires authentication: merge this router before applying
+/// `auth_middleware`.
+///
+/// ```rust,no_run
+/// # use axum::Router;
+/// # use beep_server::http::health::HealthRouter;
+/// let app: Router = Router::new().merge(
+///     HealthRouter::new()
+///         .readiness_check("database", || async { Ok(()) })
+///         .build(),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct HealthRouter {
+    checks: Vec<(String, ReadinessCheck)>,
+    check_timeout: Duration,
+}
+
+impl Default for HealthRouter {
+    fn default() -> Self {
+        Self {
+            checks: Vec::new(),
+            check_timeout: DEFAULT_READINESS_TIMEOUT,
+        }
+    }
+}
+
+impl HealthRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a readiness check run on every `GET /ready`. The check
+    /// returns an error message when the dependency is not ready.
+    ///
+    /// Checks run concurrently, and one still pending after the check timeout
+    /// fails, so a hung dependency cannot hang the probe.
+    pub fn readiness_check<F, Fut>(mut self, name: impl Into<String>, check: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        self.checks
+            .push((name.into(), Arc::new(move || Box::pin(check()))));
+        self
+    }
+
+    /// Replace [`DEFAULT_READINESS_TIMEOUT`].
+    pub fn check_timeout(mut self, timeout: Duration) -> Self {
+        self.check_timeout = timeout;
+        self
+    }
+
+    pub fn build<S>(self) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let router = Router::new().route("/health", get(health));
+
+        if self.checks.is_empty() {
+            return router;
+        }
+
+        let checks = Arc::new(self.checks);
+        let timeout = self.check_timeout;
+
+        router.route("/ready", get(move || ready(checks.clone(), timeout)))
+    }
+}
+
+/// Router serving only `GET /health`.
+pub fn health_router<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    HealthRouter::new().build()
+}
+
+async fn health() -> Response<HealthStatus> {
+    Response::OK(HealthStatus { status: "ok" })
+}
+
+/// Run the readiness checks. A failing check answers `503` naming only the
+/// check, its error message may name internal hosts and is only logged.
+async fn ready(
+    checks: Arc<Vec<(String, ReadinessCheck)>>,
+    timeout: Duration,
+) -> Result<Response<HealthStatus>, ApiError> {
+    let results = join_all(checks.iter().map(|(name, check)| async move {
+        let result = tokio::time::timeout(timeout, check())
+            .await
+            .unwrap_or_else(|_| Err(format!("timed out after {timeout:?}")));
+        (name, result)
+    }))
+    .await;
+
+    for (name, result) in results {
+        if let Err(message) = result {
+            warn!("readiness check {name} failed: {message}");
+
+            return Err(ApiError::ServiceUnavailable {
+                service: name.clone(),
+            });
+        }
+    }
+
+    Ok(Response::OK(HealthStatus { status: "ok" }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::http::health::HealthRouter;
+
+    async fn get_ready(router: Router) -> (StatusCode, String) {
+        send(router, "/ready").await
+    }
+
+    async fn send(router: Router, uri: &str) -> (StatusCode, String) {
+        let response = router
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_health_is_ok() {
+        let (status, body) = send(HealthRouter::new().build(), "/health").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains(r#""status":"ok""#));
+    }
+
+    #[tokio::test]
+    async fn test_ready_when_all_checks_pass() {
+        let router = HealthRouter::new()
+            .readiness_check("database", || async { Ok(()) })
+            .readiness_check("cache", || async { Ok(()) })
+            .build();
+
+        let (status, body) = get_ready(router).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("ok"));
+    }
+
+    #[tokio::test]
+    async fn test_failing_check_returns_503() {
+        let router = HealthRouter::new()
+            .readiness_check("database", || async { Ok(()) })
+            .readiness_check("cache", || async { Err("connection refused".to_string()) })
+            .build();
+
+        let (status, body) = get_ready(router).await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.contains("E_SERVICE_UNAVAILABLE"));
+        assert!(body.contains("cache is unavailable"));
+        assert!(!body.contains("connection refused"));
+    }
+
+    #[tokio::test]
+    async fn test_hung_check_times_out() {
+        let router = HealthRouter::new()
+            .readiness_check("database", std::future::pending)
+            .check_timeout(Duration::from_millis(20))
+            .build();
+
+        let (status, body) = tokio::time::timeout(Duration::from_secs(1), get_ready(router))
+            .await
+            .unwrap();
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.contains("database is unavailable"));
+    }
+}
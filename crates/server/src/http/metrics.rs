@@ -0,0 +1,239 @@
+#[cfg(feature = "prometheus")]
+use axum::{Router, http::header::CONTENT_TYPE, response::IntoResponse, routing::get};
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+#[cfg(feature = "prometheus")]
+use beep_telemetry::PrometheusRegistry;
+use opentelemetry::{
+    KeyValue, global,
+    metrics::{Meter, UpDownCounter},
+};
+
+/// The `http.server.active_requests` up-down counter, used as the state of
+/// [`active_requests_middleware`].
+///
+/// Instruments are bound to the meter provider installed when they are
+/// created: build this after `beep_telemetry::init`, or the counter stays a
+/// no-op for the life of the process.
+#[derive(Clone)]
+pub struct ActiveRequests(UpDownCounter<i64>);
+
+impl ActiveRequests {
+    pub fn new(meter: &Meter) -> Self {
+        Self(
+            meter
+                .i64_up_down_counter("http.server.active_requests")
+                .with_description("Number of in-flight HTTP requests")
+                .with_unit("{request}")
+                .build(),
+        )
+    }
+
+    /// Counter of the global `beep-server` meter.
+    pub fn from_global() -> Self {
+        Self::new(&global::meter("beep-server"))
+    }
+}
+
+/// Decrements the active requests counter when dropped, so the count stays
+/// correct when the handler errors, returns early or panics.
+struct ActiveRequestGuard {
+    counter: UpDownCounter<i64>,
+    attributes: [KeyValue; 1],
+}
+
+impl ActiveRequestGuard {
+    fn new(counter: UpDownCounter<i64>, method: &str) -> Self {
+        let attributes = [KeyValue::new("http.request.method", method.to_string())];
+        counter.add(1, &attributes);
+
+        Self {
+            counter,
+            attributes,
+        }
+    }
+}
+
+impl Drop for ActiveRequestGuard {
+    fn drop(&mut self) {
+        self.counter.add(-1, &self.attributes);
+    }
+}
+
+/// Track in-flight requests in the `http.server.active_requests` up-down
+/// counter.
+///
+/// ```rust,no_run
+/// # use axum::{Router, middleware};
+/// # use beep_server::http::metrics::{ActiveRequests, active_requests_middleware};
+/// // After `beep_telemetry::init`:
+/// let app: Router = Router::new().layer(middleware::from_fn_with_state(
+///     ActiveRequests::from_global(),
+///     active_requests_middleware,
+/// ));
+/// ```
+pub async fn active_requests_middleware(
+    State(ActiveRequests(counter)): State<ActiveRequests>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let _guard = ActiveRequestGuard::new(counter, req.method().as_str());
+
+    next.run(req).await
+}
+
+/// Router serving `GET /metrics` in the Prometheus text format.
+///
+/// Like the health routes, scraping is unauthenticated: merge this router
+/// before applying `auth_middleware`.
+///
+/// ```rust,no_run
+/// # use axum::Router;
+/// # use beep_server::http::metrics::prometheus_router;
+/// # fn example(guard: &beep_telemetry::OtelGuard) {
+/// if let Some(registry) = guard.prometheus_registry() {
+///     let app: Router = Router::new().merge(prometheus_router(registry));
+/// }
+/// # }
+/// ```
+#[cfg(feature = "prometheus")]
+pub fn prometheus_router<S>(registry: PrometheusRegistry) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new().route(
+        "/metrics",
+        get(move || async move {
+            match registry.render() {
+                Ok(body) => ([(CONTENT_TYPE, registry.content_type())], body).into_response(),
+                Err(e) => crate::ApiError::Unknown {
+                    message: e.to_string(),
+                }
+                .into_response(),
+            }
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+        middleware::from_fn_with_state,
+        routing::get,
+    };
+    use opentelemetry::metrics::MeterProvider as _;
+    use opentelemetry_sdk::metrics::{
+        InMemoryMetricExporter, PeriodicReader, SdkMeterProvider,
+        data::{AggregatedMetrics, MetricData},
+    };
+    use tower::ServiceExt;
+
+    use crate::{
+        ApiError,
+        http::{
+            metrics::{ActiveRequests, active_requests_middleware},
+            panic::catch_panic_layer,
+        },
+    };
+
+    /// Current value of the active requests counter, summed over methods.
+    fn active_requests(provider: &SdkMeterProvider, exporter: &InMemoryMetricExporter) -> i64 {
+        provider.force_flush().unwrap();
+        let metrics = exporter.get_finished_metrics().unwrap();
+
+        metrics
+            .last()
+            .unwrap()
+            .scope_metrics()
+            .flat_map(|scope| scope.metrics())
+            .find(|metric| metric.name() == "http.server.active_requests")
+            .map(|metric| match metric.data() {
+                AggregatedMetrics::I64(MetricData::Sum(sum)) => {
+                    sum.data_points().map(|point| point.value()).sum()
+                }
+                _ => panic!("unexpected active requests data"),
+            })
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_active_requests_decremented_on_error_and_panic() {
+        let exporter = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone()).build())
+            .build();
+        let active = ActiveRequests::new(&provider.meter("test"));
+
+        let router = Router::new()
+            .route(
+                "/error",
+                get({
+                    let (provider, exporter) = (provider.clone(), exporter.clone());
+                    move || async move {
+                        assert_eq!(active_requests(&provider, &exporter), 1);
+                        Err::<(), _>(ApiError::Unknown {
+                            message: "boom".to_string(),
+                        })
+                    }
+                }),
+            )
+            .route(
+                "/panic",
+                get(|| async {
+                    panic!("boom");
+                    #[allow(unreachable_code)]
+                    ""
+                }),
+            )
+            .layer(from_fn_with_state(active, active_requests_middleware))
+            .layer(catch_panic_layer());
+
+        for (uri, status) in [
+            ("/error", StatusCode::INTERNAL_SERVER_ERROR),
+            ("/panic", StatusCode::INTERNAL_SERVER_ERROR),
+        ] {
+            let response = router
+                .clone()
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), status);
+            assert_eq!(active_requests(&provider, &exporter), 0, "{uri}");
+        }
+    }
+}
+
+#[cfg(all(test, feature = "prometheus"))]
+mod prometheus_tests {
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode, header::CONTENT_TYPE},
+    };
+    use beep_telemetry::PrometheusRegistry;
+    use tower::ServiceExt;
+
+    use crate::http::metrics::prometheus_router;
+
+    #[tokio::test]
+    async fn test_prometheus_router_renders_text_format() {
+        let response = prometheus_router::<()>(PrometheusRegistry::default())
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(
+            response.headers()[CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/plain")
+        );
+    }
+}
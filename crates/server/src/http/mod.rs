@@ -1,6 +1,9 @@
 use axum::{
     extract::{Request, State},
-    http::{HeaderValue, StatusCode, header::AUTHORIZATION},
+    http::{
+        HeaderMap, HeaderValue,
+        header::{AUTHORIZATION, COOKIE},
+    },
     middleware::Next,
     response::Response,
 };
@@ -8,24 +11,25 @@ use axum::{
 use beep_auth::{AuthError, AuthRepository, HasAuthRepository, Token};
 use tracing::{debug, error};
 
-pub mod response;
+use crate::ApiError;
 
-#[derive(Debug)]
-pub enum MiddlewareError {
-    MissingAuthHeader,
-    InvalidAuthHeader,
-    AuthenticationFailed(AuthError),
-}
+pub use cors::cors_layer;
+pub use extractor::AuthenticatedUser;
+pub use health::{HealthRouter, health_router};
+pub use telemetry::TelemetryLayer;
 
-impl From<MiddlewareError> for StatusCode {
-    fn from(error: MiddlewareError) -> Self {
-        match error {
-            MiddlewareError::MissingAuthHeader => StatusCode::UNAUTHORIZED,
-            MiddlewareError::InvalidAuthHeader => StatusCode::UNAUTHORIZED,
-            MiddlewareError::AuthenticationFailed(_) => StatusCode::UNAUTHORIZED,
-        }
-    }
-}
+pub mod body_limit;
+pub mod compression;
+pub mod cors;
+pub mod extractor;
+pub mod health;
+pub mod metrics;
+pub mod panic;
+pub mod request_id;
+pub mod response;
+pub mod telemetry;
+pub mod tenant;
+pub mod timeout;
 
 pub async fn extract_token_from_bearer(auth_header: &HeaderValue) -> Result<Token, AuthError> {
     let auth_str = auth_header.to_str().map_err(|_| AuthError::TokenNotFound)?;
@@ -41,31 +45,73 @@ pub async fn extract_token_from_bearer(auth_header: &HeaderValue) -> Result<Toke
     Ok(Token::new(token.to_string()))
 }
 
+/// Read the value of the `name` cookie from the `Cookie` headers.
+pub fn extract_cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.trim_matches('"'))
+        .filter(|value| !value.is_empty())
+}
+
+/// Extract the access token from the `Authorization: Bearer` header or,
+/// failing that, from the `cookie_name` cookie.
+///
+/// The header always takes priority so API clients are unaffected by a
+/// stale cookie.
+pub async fn extract_token(
+    headers: &HeaderMap,
+    cookie_name: Option<&str>,
+) -> Result<Token, AuthError> {
+    if let Some(auth_header) = headers.get(AUTHORIZATION)
+        && let Ok(token) = extract_token_from_bearer(auth_header).await
+    {
+        return Ok(token);
+    }
+
+    cookie_name
+        .and_then(|name| extract_cookie(headers, name))
+        .map(Token::new)
+        .ok_or(AuthError::TokenNotFound)
+}
+
+/// Where the auth middlewares look for an access token besides the
+/// `Authorization` header.
+///
+/// Cookie authentication is a concern of the HTTP layer, so it is configured
+/// on the middleware state rather than on the [`AuthRepository`], typically
+/// from the `cookie_name` of [`AuthArgs`](crate::args::auth::AuthArgs).
+pub trait HasAuthCookie {
+    /// Name of the cookie holding the access token, used when a request has
+    /// no `Authorization` header. `None` disables cookie authentication.
+    fn auth_cookie_name(&self) -> Option<&str> {
+        None
+    }
+}
+
 pub async fn auth_middleware<T>(
     State(state): State<T>,
     mut req: Request,
     next: Next,
-) -> Result<Response, StatusCode>
+) -> Result<Response, ApiError>
 where
-    T: HasAuthRepository + Send + Sync,
+    T: HasAuthRepository + HasAuthCookie + Send + Sync,
 {
-    let auth_header = req
-        .headers()
-        .get(AUTHORIZATION)
-        .ok_or(MiddlewareError::MissingAuthHeader)?;
-
-    let token = extract_token_from_bearer(auth_header)
-        .await
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let token = extract_token(req.headers(), state.auth_cookie_name()).await?;
 
     let identity = state
         .auth_repository()
         .identify(token.as_str())
         .await
-        .map_err(|e| {
-            error!("auth middleware: failed to identity user {:?}", e);
-            MiddlewareError::AuthenticationFailed(e)
-        })?;
+        .inspect_err(|e| error!("auth middleware: failed to identity user {:?}", e))?;
+
+    if identity.is_expired_with_leeway(state.auth_repository().leeway()) {
+        return Err(AuthError::Expired.into());
+    }
 
     debug!(
         "auth middleware: successfully identified user: {}",
@@ -76,3 +122,288 @@ where
 
     Ok(next.run(req).await)
 }
+
+/// Like [`auth_middleware`], but lets requests without a token through.
+///
+/// - No `Authorization` header and no auth cookie: the request proceeds
+///   without an [`Identity`](beep_auth::Identity) extension, handlers extract
+///   `Option<AuthenticatedUser>`.
+/// - Valid token: the identity is inserted as usual.
+/// - Malformed `Authorization` header, invalid or expired token: rejected with
+///   `401`, a client sending bad credentials should find out rather than
+///   silently being treated as anonymous.
+pub async fn optional_auth_middleware<T>(
+    State(state): State<T>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, ApiError>
+where
+    T: HasAuthRepository + HasAuthCookie + Send + Sync,
+{
+    let cookie_name = state.auth_cookie_name();
+    let has_credentials = req.headers().contains_key(AUTHORIZATION)
+        || cookie_name.is_some_and(|name| extract_cookie(req.headers(), name).is_some());
+
+    if !has_credentials {
+        return Ok(next.run(req).await);
+    }
+
+    let token = extract_token(req.headers(), cookie_name)
+        .await
+        .map_err(|_| ApiError::InvalidToken {
+            message: "malformed authorization header".to_string(),
+        })?;
+
+    let identity = state
+        .auth_repository()
+        .identify(token.as_str())
+        .await
+        .inspect_err(|e| error!("optional auth middleware: failed to identity user {:?}", e))?;
+
+    if identity.is_expired_with_leeway(state.auth_repository().leeway()) {
+        return Err(AuthError::Expired.into());
+    }
+
+    req.extensions_mut().insert(identity);
+
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{
+        HeaderMap, HeaderValue,
+        header::{AUTHORIZATION, COOKIE},
+    };
+    use axum::{
+        Extension, Router,
+        body::Body,
+        http::{Request, StatusCode},
+        middleware::from_fn_with_state,
+        routing::get,
+    };
+    use beep_auth::{AuthError, AuthRepository, Claims, Client, HasAuthRepository, Identity};
+    use tower::ServiceExt;
+
+    use crate::http::{HasAuthCookie, auth_middleware, extract_token, optional_auth_middleware};
+
+    #[derive(Clone)]
+    struct StubAuthRepository;
+
+    impl AuthRepository for StubAuthRepository {
+        async fn validate_token(&self, _token: &str) -> Result<Claims, AuthError> {
+            Err(AuthError::Internal {
+                message: "not used".to_string(),
+            })
+        }
+
+        async fn identify(&self, token: &str) -> Result<Identity, AuthError> {
+            match token {
+                "unreachable" => Err(AuthError::Network {
+                    message: "connection refused".to_string(),
+                }),
+                "valid" | "expired" => Ok(Identity::Client(Client {
+                    id: "client-id".to_string(),
+                    client_id: "client".to_string(),
+                    roles: vec![],
+                    scopes: vec![],
+                    expires_at: (token == "expired").then_some(0),
+                })),
+                _ => Err(AuthError::InvalidToken {
+                    message: "bad token".to_string(),
+                }),
+            }
+        }
+    }
+
+    impl HasAuthRepository for StubAuthRepository {
+        type AuthRepo = Self;
+
+        fn auth_repository(&self) -> &Self::AuthRepo {
+            self
+        }
+    }
+
+    impl HasAuthCookie for StubAuthRepository {
+        fn auth_cookie_name(&self) -> Option<&str> {
+            Some("session")
+        }
+    }
+
+    fn identity_router() -> Router {
+        Router::new().route(
+            "/",
+            get(|identity: Option<Extension<Identity>>| async move {
+                identity.map_or("anonymous".to_string(), |i| i.id().to_string())
+            }),
+        )
+    }
+
+    fn optional_auth_router() -> Router {
+        identity_router().layer(from_fn_with_state(
+            StubAuthRepository,
+            optional_auth_middleware::<StubAuthRepository>,
+        ))
+    }
+
+    fn auth_router() -> Router {
+        identity_router().layer(from_fn_with_state(
+            StubAuthRepository,
+            auth_middleware::<StubAuthRepository>,
+        ))
+    }
+
+    async fn call_optional_auth(authorization: Option<&str>) -> (StatusCode, String) {
+        call(optional_auth_router(), authorization).await
+    }
+
+    async fn call(router: Router, authorization: Option<&str>) -> (StatusCode, String) {
+        call_with_cookie(router, authorization, None).await
+    }
+
+    async fn call_with_cookie(
+        router: Router,
+        authorization: Option<&str>,
+        cookie: Option<&str>,
+    ) -> (StatusCode, String) {
+        let mut request = Request::builder().uri("/");
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, authorization);
+        }
+        if let Some(cookie) = cookie {
+            request = request.header(COOKIE, cookie);
+        }
+
+        let response = router
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_optional_auth_without_token_is_anonymous() {
+        let (status, body) = call_optional_auth(None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "anonymous");
+    }
+
+    #[tokio::test]
+    async fn test_optional_auth_with_valid_token_identifies() {
+        let (status, body) = call_optional_auth(Some("Bearer valid")).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "client-id");
+    }
+
+    #[tokio::test]
+    async fn test_optional_auth_with_invalid_token_is_rejected() {
+        let (status, body) = call_optional_auth(Some("Bearer invalid")).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(body.contains("E_UNAUTHORIZED"));
+    }
+
+    #[tokio::test]
+    async fn test_optional_auth_with_malformed_header_is_rejected() {
+        for authorization in ["Bearer", "bearer valid", "Basic dXNlcjpwYXNz"] {
+            let (status, body) = call_optional_auth(Some(authorization)).await;
+
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{authorization}");
+            assert!(body.contains("E_UNAUTHORIZED"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_optional_auth_with_expired_token_is_rejected() {
+        let (status, body) = call_optional_auth(Some("Bearer expired")).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(body.contains("E_TOKEN_EXPIRED"));
+    }
+
+    #[tokio::test]
+    async fn test_auth_with_valid_token_identifies() {
+        let (status, body) = call(auth_router(), Some("Bearer valid")).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "client-id");
+    }
+
+    #[tokio::test]
+    async fn test_auth_with_expired_token_is_rejected() {
+        let (status, body) = call(auth_router(), Some("Bearer expired")).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(body.contains("E_TOKEN_EXPIRED"));
+    }
+
+    #[tokio::test]
+    async fn test_auth_with_cookie_identifies() {
+        let (status, body) = call_with_cookie(auth_router(), None, Some("session=valid")).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "client-id");
+    }
+
+    #[tokio::test]
+    async fn test_auth_prefers_header_over_cookie() {
+        let (status, body) =
+            call_with_cookie(auth_router(), Some("Bearer expired"), Some("session=valid")).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(body.contains("E_TOKEN_EXPIRED"));
+    }
+
+    #[tokio::test]
+    async fn test_optional_auth_with_unreachable_idp() {
+        let (status, body) = call_optional_auth(Some("Bearer unreachable")).await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.contains("E_IDP_UNAVAILABLE"));
+        assert!(!body.contains("connection refused"));
+    }
+
+    #[tokio::test]
+    async fn test_extract_token_prefers_header_over_cookie() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_static("Bearer from-header"),
+        );
+        headers.insert(COOKIE, HeaderValue::from_static("session=from-cookie"));
+
+        let token = extract_token(&headers, Some("session")).await.unwrap();
+
+        assert_eq!(token.as_str(), "from-header");
+    }
+
+    #[tokio::test]
+    async fn test_extract_token_from_cookie() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            COOKIE,
+            HeaderValue::from_static("theme=dark; session=from-cookie"),
+        );
+
+        let token = extract_token(&headers, Some("session")).await.unwrap();
+
+        assert_eq!(token.as_str(), "from-cookie");
+    }
+
+    #[tokio::test]
+    async fn test_extract_token_not_found() {
+        let mut headers = HeaderMap::new();
+        headers.insert(COOKIE, HeaderValue::from_static("session=from-cookie"));
+
+        let result = extract_token(&headers, None).await;
+
+        assert!(matches!(result, Err(AuthError::TokenNotFound)));
+    }
+}
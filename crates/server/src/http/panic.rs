@@ -0,0 +1,78 @@
+use std::any::Any;
+
+use axum::response::{IntoResponse, Response};
+use tower_http::catch_panic::CatchPanicLayer;
+use tracing::error;
+
+use crate::ApiError;
+
+type PanicHandler = fn(Box<dyn Any + Send + 'static>) -> Response;
+
+/// Build a layer turning handler panics into a `500` with the standard
+/// `ApiErrorResponse` body, instead of the connection being reset.
+///
+/// The panic payload is logged with `tracing::error!` inside the current
+/// request span, so it is exported like any other error. The payload is
+/// kept out of the response body to avoid leaking internals to clients.
+/// Apply it inside the request id and tracing layers so the log carries
+/// their context.
+pub fn catch_panic_layer() -> CatchPanicLayer<PanicHandler> {
+    CatchPanicLayer::custom(handle_panic as PanicHandler)
+}
+
+fn handle_panic(payload: Box<dyn Any + Send + 'static>) -> Response {
+    let message = payload
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| payload.downcast_ref::<&str>().copied())
+        .unwrap_or("unknown panic payload");
+
+    error!(panic.message = message, "handler panicked");
+
+    ApiError::Unknown {
+        message: "handler panicked".to_string(),
+    }
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use tower::ServiceExt;
+
+    use crate::http::panic::catch_panic_layer;
+
+    #[tokio::test]
+    async fn test_panic_returns_structured_500() {
+        let router = Router::new()
+            .route(
+                "/",
+                get(|| async {
+                    panic!("boom");
+                    #[allow(unreachable_code)]
+                    ""
+                }),
+            )
+            .layer(catch_panic_layer());
+
+        let response = router
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body.contains("E_INTERNAL_SERVER_ERROR"));
+        assert!(!body.contains("boom"));
+    }
+}
@@ -0,0 +1,160 @@
+use axum::{
+    extract::Request,
+    http::{HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::{global, propagation::Extractor};
+use tracing::{Instrument, field::Empty, info_span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+
+use crate::http::telemetry::RequestSpan;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Incoming request ids longer than this are replaced by a generated one.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Id of the current request, inserted in the request extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+pub(crate) struct HeaderExtractor<'a>(pub(crate) &'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+/// Tag every request with an id and join the upstream trace.
+///
+/// - Reuses the incoming `x-request-id` header, or generates a UUID.
+/// - When an outer [`TelemetryLayer`](crate::http::TelemetryLayer) already
+///   opened the request span, records the id in its `request_id` field and
+///   leaves the trace parent to it. Otherwise runs the request in an
+///   `http.request` span carrying the id, whose parent is
+///   extracted from the W3C `traceparent` header through the global text map
+///   propagator.
+/// - Echoes the id in the `x-request-id` response header.
+pub async fn request_id_middleware(mut req: Request, next: Next) -> Response {
+    let request_id = req
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let span = match req.extensions().get::<RequestSpan>() {
+        Some(RequestSpan(span)) => {
+            span.record("request_id", request_id.as_str());
+            None
+        }
+        None => {
+            let parent_cx = global::get_text_map_propagator(|propagator| {
+                propagator.extract(&HeaderExtractor(req.headers()))
+            });
+
+            let span = info_span!(
+                "http.request",
+                request_id = %request_id,
+                http.request.method = %req.method(),
+                url.path = %req.uri().path(),
+                tenant.id = Empty,
+            );
+            let _ = span.set_parent(parent_cx);
+            req.extensions_mut().insert(RequestSpan(span.clone()));
+            Some(span)
+        }
+    };
+
+    req.extensions_mut().insert(RequestId(request_id.clone()));
+
+    let mut response = match span {
+        Some(span) => next.run(req).instrument(span).await,
+        None => next.run(req).await,
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Extension, Router,
+        body::Body,
+        http::{Request, StatusCode},
+        middleware::from_fn,
+        routing::get,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    use crate::http::request_id::{REQUEST_ID_HEADER, RequestId, request_id_middleware};
+
+    /// Send a request with the given `x-request-id` and return the id seen by
+    /// the handler and the echoed header.
+    async fn send(request_id: Option<&str>) -> (String, String) {
+        let router = Router::new()
+            .route(
+                "/",
+                get(|Extension(RequestId(id)): Extension<RequestId>| async move { id }),
+            )
+            .layer(from_fn(request_id_middleware));
+
+        let mut request = Request::get("/");
+        if let Some(request_id) = request_id {
+            request = request.header(REQUEST_ID_HEADER, request_id);
+        }
+
+        let response = router
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let echoed = response.headers()[REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        (String::from_utf8(body.to_vec()).unwrap(), echoed)
+    }
+
+    #[tokio::test]
+    async fn test_reuses_incoming_request_id() {
+        let (seen, echoed) = send(Some("req-42")).await;
+
+        assert_eq!(seen, "req-42");
+        assert_eq!(echoed, "req-42");
+    }
+
+    #[tokio::test]
+    async fn test_generates_request_id_when_missing() {
+        let (seen, echoed) = send(None).await;
+
+        assert!(Uuid::parse_str(&seen).is_ok());
+        assert_eq!(echoed, seen);
+    }
+
+    #[tokio::test]
+    async fn test_replaces_overlong_request_id() {
+        let overlong = "a".repeat(129);
+
+        let (seen, echoed) = send(Some(&overlong)).await;
+
+        assert!(Uuid::parse_str(&seen).is_ok());
+        assert_eq!(echoed, seen);
+    }
+}
@@ -0,0 +1,354 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use axum::{
+    extract::MatchedPath,
+    http::{HeaderMap, HeaderName, HeaderValue, Request, Response},
+};
+use opentelemetry::{global, propagation::Injector};
+use tower::{Layer, Service};
+use tracing::{Instrument, Span, field::Empty, info_span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::http::request_id::HeaderExtractor;
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+/// Request span opened by this crate, inserted in the request extensions by
+/// [`TelemetryLayer`] and
+/// [`request_id_middleware`](crate::http::request_id::request_id_middleware).
+///
+/// Inner middlewares record their fields on it rather than on whatever span is
+/// current, and only the outermost one joins the upstream trace.
+#[derive(Debug, Clone)]
+pub(crate) struct RequestSpan(pub(crate) Span);
+
+/// Layer instrumenting every request with an OpenTelemetry server span.
+///
+/// - Joins the upstream trace from the incoming headers (W3C `traceparent`
+///   through the global text map propagator), unless an outer
+///   [`request_id_middleware`](crate::http::request_id::request_id_middleware)
+///   already opened the request span. Spans from other layers, e.g. tower-http
+///   `TraceLayer`, don't prevent it.
+/// - Names the span from the method and the matched route, e.g.
+///   `GET /channels/{id}`, so spans aggregate per route and not per URL.
+/// - Records the response status code and the latency in milliseconds, and
+///   flags `5xx` responses as errors.
+/// - Injects the span context in the response headers.
+/// - Declares empty `request_id` and `tenant.id` fields, filled by an inner
+///   [`request_id_middleware`](crate::http::request_id::request_id_middleware)
+///   and [`tenant_middleware`](crate::http::tenant::tenant_middleware).
+///
+/// Apply it with `Router::layer` so the matched route is known:
+///
+/// ```rust,no_run
+/// # use axum::{Router, routing::get};
+/// # use beep_server::http::TelemetryLayer;
+/// let app: Router = Router::new()
+///     .route("/", get(|| async { "ok" }))
+///     .layer(TelemetryLayer::new());
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TelemetryLayer;
+
+impl TelemetryLayer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for TelemetryLayer {
+    type Service = TelemetryService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TelemetryService { inner }
+    }
+}
+
+/// Service produced by [`TelemetryLayer`].
+#[derive(Debug, Clone)]
+pub struct TelemetryService<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for TelemetryService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let parent_cx = req.extensions().get::<RequestSpan>().is_none().then(|| {
+            global::get_text_map_propagator(|propagator| {
+                propagator.extract(&HeaderExtractor(req.headers()))
+            })
+        });
+
+        let route = req
+            .extensions()
+            .get::<MatchedPath>()
+            .map(|path| path.as_str().to_string());
+        let name = match &route {
+            Some(route) => format!("{} {}", req.method(), route),
+            None => req.method().to_string(),
+        };
+
+        let span = info_span!(
+            "http.server.request",
+            otel.name = %name,
+            otel.kind = "server",
+            otel.status_code = Empty,
+            http.request.method = %req.method(),
+            http.route = route,
+            url.path = %req.uri().path(),
+            http.response.status_code = Empty,
+            latency_ms = Empty,
+            request_id = Empty,
+            tenant.id = Empty,
+        );
+        if let Some(parent_cx) = parent_cx {
+            let _ = span.set_parent(parent_cx);
+        }
+        req.extensions_mut().insert(RequestSpan(span.clone()));
+
+        let start = Instant::now();
+        let future = span.in_scope(|| self.inner.call(req));
+
+        Box::pin(async move {
+            let result = future.instrument(span.clone()).await;
+            span.record("latency_ms", start.elapsed().as_millis() as u64);
+
+            let Ok(mut response) = result else {
+                span.record("otel.status_code", "ERROR");
+                return result;
+            };
+
+            span.record("http.response.status_code", response.status().as_u16());
+            if response.status().is_server_error() {
+                span.record("otel.status_code", "ERROR");
+            }
+
+            global::get_text_map_propagator(|propagator| {
+                propagator
+                    .inject_context(&span.context(), &mut HeaderInjector(response.headers_mut()))
+            });
+
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        body::Body,
+        extract,
+        http::{Request, StatusCode},
+        middleware::{Next, from_fn},
+        response::Response,
+        routing::get,
+    };
+    use opentelemetry::{
+        Value, global,
+        trace::{SpanId, TracerProvider as _},
+    };
+    use opentelemetry_sdk::{
+        propagation::TraceContextPropagator,
+        trace::{InMemorySpanExporter, SdkTracerProvider, SpanData},
+    };
+    use tower::ServiceExt;
+    use tracing::{Instrument, info_span};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use crate::http::{request_id::request_id_middleware, telemetry::TelemetryLayer};
+
+    const UPSTREAM_SPAN_ID: &str = "00f067aa0ba902b7";
+
+    /// Send a traced request through `router` and return the exported spans.
+    async fn export_spans(router: Router) -> Vec<SpanData> {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let response = router
+            .oneshot(
+                Request::get("/channels/42")
+                    .header(
+                        "traceparent",
+                        format!("00-4bf92f3577b34da6a3ce929d0e0e4736-{UPSTREAM_SPAN_ID}-01"),
+                    )
+                    .header("x-request-id", "req-1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        exporter.get_finished_spans().unwrap()
+    }
+
+    /// Stand-in for a span opened by another layer, e.g. tower-http
+    /// `TraceLayer`.
+    async fn foreign_span(req: extract::Request, next: Next) -> Response {
+        next.run(req).instrument(info_span!("app.request")).await
+    }
+
+    fn attribute<'a>(span: &'a SpanData, key: &str) -> Option<&'a Value> {
+        span.attributes
+            .iter()
+            .find(|attribute| attribute.key.as_str() == key)
+            .map(|attribute| &attribute.value)
+    }
+
+    #[tokio::test]
+    async fn test_joins_upstream_trace_and_injects_context() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let router = Router::new()
+            .route("/channels/{id}", get(|| async { "ok" }))
+            .layer(TelemetryLayer::new());
+
+        let response = router
+            .oneshot(
+                Request::get("/channels/42")
+                    .header(
+                        "traceparent",
+                        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+                    )
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let traceparent = response.headers()["traceparent"].to_str().unwrap();
+        assert!(traceparent.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"));
+        assert!(!traceparent.contains("00f067aa0ba902b7"));
+    }
+
+    #[tokio::test]
+    async fn test_request_id_recorded_on_telemetry_span() {
+        let router = Router::new()
+            .route("/channels/{id}", get(|| async { "ok" }))
+            .layer(from_fn(request_id_middleware))
+            .layer(TelemetryLayer::new());
+
+        let spans = export_spans(router).await;
+
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].name, "GET /channels/{id}");
+        assert_eq!(
+            spans[0].parent_span_id,
+            SpanId::from_hex(UPSTREAM_SPAN_ID).unwrap()
+        );
+        assert_eq!(
+            attribute(&spans[0], "request_id"),
+            Some(&Value::from("req-1"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_telemetry_span_nested_in_request_id_span() {
+        let router = Router::new()
+            .route("/channels/{id}", get(|| async { "ok" }))
+            .layer(TelemetryLayer::new())
+            .layer(from_fn(request_id_middleware));
+
+        let spans = export_spans(router).await;
+
+        let outer = spans
+            .iter()
+            .find(|span| span.name == "http.request")
+            .unwrap();
+        let inner = spans
+            .iter()
+            .find(|span| span.name == "GET /channels/{id}")
+            .unwrap();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(
+            outer.parent_span_id,
+            SpanId::from_hex(UPSTREAM_SPAN_ID).unwrap()
+        );
+        assert_eq!(inner.parent_span_id, outer.span_context.span_id());
+        assert_eq!(inner.span_context.trace_id(), outer.span_context.trace_id());
+    }
+
+    #[tokio::test]
+    async fn test_joins_upstream_trace_inside_foreign_span() {
+        let router = Router::new()
+            .route("/channels/{id}", get(|| async { "ok" }))
+            .layer(from_fn(request_id_middleware))
+            .layer(TelemetryLayer::new())
+            .layer(from_fn(foreign_span));
+
+        let spans = export_spans(router).await;
+
+        let request = spans
+            .iter()
+            .find(|span| span.name == "GET /channels/{id}")
+            .unwrap();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(
+            request.parent_span_id,
+            SpanId::from_hex(UPSTREAM_SPAN_ID).unwrap()
+        );
+        assert_eq!(
+            attribute(request, "request_id"),
+            Some(&Value::from("req-1"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_id_span_joins_upstream_trace_inside_foreign_span() {
+        let router = Router::new()
+            .route("/channels/{id}", get(|| async { "ok" }))
+            .layer(from_fn(request_id_middleware))
+            .layer(from_fn(foreign_span));
+
+        let spans = export_spans(router).await;
+
+        let request = spans
+            .iter()
+            .find(|span| span.name == "http.request")
+            .unwrap();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(
+            request.parent_span_id,
+            SpanId::from_hex(UPSTREAM_SPAN_ID).unwrap()
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use std::{collections::HashSet, sync::Arc};
+
+use axum::{
+    extract::{Request, State},
+    http::HeaderName,
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::{KeyValue, baggage::BaggageExt, context::FutureExt};
+use tracing::{Span, warn};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::{ApiError, http::telemetry::RequestSpan};
+
+pub const TENANT_ID_KEY: &str = "tenant.id";
+pub const DEFAULT_TENANT_HEADER: &str = "x-tenant-id";
+const MAX_TENANT_ID_LEN: usize = 64;
+
+/// Tenant of the current request, inserted in the request extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tenant(pub String);
+
+/// Where the tenant is read from and which values are accepted.
+///
+/// Tenant ids always have to be short and made of ASCII alphanumerics, `-` or
+/// `_`. When an allowlist is set, only its ids are accepted, which bounds the
+/// cardinality of the `tenant.id` attribute.
+#[derive(Debug, Clone)]
+pub struct TenantConfig {
+    pub header: HeaderName,
+    pub allowlist: Option<Arc<HashSet<String>>>,
+}
+
+impl Default for TenantConfig {
+    fn default() -> Self {
+        Self {
+            header: HeaderName::from_static(DEFAULT_TENANT_HEADER),
+            allowlist: None,
+        }
+    }
+}
+
+impl TenantConfig {
+    pub fn with_allowlist(mut self, tenants: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.allowlist = Some(Arc::new(tenants.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    pub fn is_valid(&self, tenant: &str) -> bool {
+        let well_formed = !tenant.is_empty()
+            && tenant.len() <= MAX_TENANT_ID_LEN
+            && tenant
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+        well_formed
+            && self
+                .allowlist
+                .as_ref()
+                .is_none_or(|allowlist| allowlist.contains(tenant))
+    }
+}
+
+/// Attach the request tenant to the `tenant.id` field of the request span and
+/// to a `tenant.id` baggage entry.
+///
+/// The field is recorded on the request span opened by an outer
+/// [`TelemetryLayer`](crate::http::TelemetryLayer) or
+/// [`request_id_middleware`](crate::http::request_id::request_id_middleware),
+/// so run this middleware inside one of them. Without either, it falls back to
+/// the current span, which only keeps the field if it declares it.
+///
+/// The baggage is added to the OpenTelemetry context of the current span, so
+/// `opentelemetry::Context::current()` in the rest of the request carries both
+/// the request span and the tenant, e.g. for the text map propagator to inject
+/// them into outgoing requests. It is not copied onto the attributes of child
+/// spans, so filter traces on the request span's `tenant.id`.
+///
+/// The tenant is kept off metric attributes: without an allowlist, tenant ids
+/// are only checked for shape and their cardinality is unbounded.
+///
+/// Requests without a tenant header go through untouched; requests with an
+/// invalid or unknown tenant are rejected with [`ApiError::InvalidTenant`].
+pub async fn tenant_middleware(
+    State(config): State<TenantConfig>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let Some(header) = req.headers().get(&config.header) else {
+        return Ok(next.run(req).await);
+    };
+
+    let tenant = header
+        .to_str()
+        .ok()
+        .filter(|tenant| config.is_valid(tenant))
+        .map(str::to_string)
+        .ok_or_else(|| {
+            warn!("tenant middleware: rejected invalid tenant id");
+            ApiError::InvalidTenant
+        })?;
+
+    match req.extensions().get::<RequestSpan>() {
+        Some(RequestSpan(span)) => span.record(TENANT_ID_KEY, tenant.as_str()),
+        None => Span::current().record(TENANT_ID_KEY, tenant.as_str()),
+    };
+    let cx = Span::current()
+        .context()
+        .with_baggage(vec![KeyValue::new(TENANT_ID_KEY, tenant.clone())]);
+
+    req.extensions_mut().insert(Tenant(tenant));
+
+    Ok(next.run(req).with_context(cx).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Extension, Router,
+        body::Body,
+        http::{Request, StatusCode},
+        middleware::from_fn_with_state,
+        routing::get,
+    };
+    use http_body_util::BodyExt;
+    use opentelemetry::{
+        Context, Value,
+        baggage::BaggageExt,
+        trace::{TraceContextExt, TracerProvider as _},
+    };
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+    use tower::ServiceExt;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use crate::http::{
+        TelemetryLayer,
+        tenant::{TENANT_ID_KEY, Tenant, TenantConfig, tenant_middleware},
+    };
+
+    fn router(config: TenantConfig) -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(|tenant: Option<Extension<Tenant>>| async move {
+                    tenant.map(|Extension(Tenant(id))| id).unwrap_or_default()
+                }),
+            )
+            .layer(from_fn_with_state(config, tenant_middleware))
+    }
+
+    async fn get_with_tenant(router: Router, tenant: Option<&str>) -> (StatusCode, String) {
+        let mut request = Request::get("/");
+        if let Some(tenant) = tenant {
+            request = request.header("x-tenant-id", tenant);
+        }
+
+        let response = router
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_missing_header_passes_through() {
+        let config = TenantConfig::default().with_allowlist(["acme"]);
+
+        let (status, body) = get_with_tenant(router(config), None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "");
+    }
+
+    #[tokio::test]
+    async fn test_allowlisted_tenant_accepted() {
+        let config = TenantConfig::default().with_allowlist(["acme"]);
+
+        let (status, body) = get_with_tenant(router(config), Some("acme")).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "acme");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_tenants_rejected() {
+        let allowlisted = TenantConfig::default().with_allowlist(["acme"]);
+        let too_long = "a".repeat(65);
+
+        for (config, tenant) in [
+            (allowlisted, "globex"),
+            (TenantConfig::default(), "acme corp"),
+            (TenantConfig::default(), "../acme"),
+            (TenantConfig::default(), ""),
+            (TenantConfig::default(), too_long.as_str()),
+        ] {
+            let (status, body) = get_with_tenant(router(config), Some(tenant)).await;
+
+            assert_eq!(status, StatusCode::BAD_REQUEST, "tenant {tenant:?}");
+            assert!(body.contains("E_INVALID_TENANT"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tenant_baggage_carries_request_span() {
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let router = Router::new()
+            .route(
+                "/",
+                get(|| async {
+                    let cx = Context::current();
+                    let tenant = cx.baggage().get(TENANT_ID_KEY).map(|v| v.to_string());
+                    let span_id = tracing::Span::current()
+                        .context()
+                        .span()
+                        .span_context()
+                        .span_id();
+
+                    assert_eq!(cx.span().span_context().span_id(), span_id);
+                    assert!(cx.span().span_context().is_valid());
+                    tenant.unwrap_or_default()
+                }),
+            )
+            .layer(from_fn_with_state(
+                TenantConfig::default(),
+                tenant_middleware,
+            ))
+            .layer(TelemetryLayer::new());
+
+        let (status, body) = get_with_tenant(router, Some("acme")).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "acme");
+    }
+
+    #[tokio::test]
+    async fn test_tenant_recorded_on_request_span() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let router = router(TenantConfig::default()).layer(TelemetryLayer::new());
+        let (status, _) = get_with_tenant(router, Some("acme")).await;
+
+        let spans = exporter.get_finished_spans().unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(spans.len(), 1);
+        assert!(
+            spans[0]
+                .attributes
+                .iter()
+                .any(|attribute| attribute.key.as_str() == TENANT_ID_KEY
+                    && attribute.value == Value::from("acme"))
+        );
+    }
+}
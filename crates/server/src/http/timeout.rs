@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use tokio::{sync::watch, time::Instant};
+use tracing::warn;
+
+use crate::ApiError;
+
+/// Maximum duration of a request, used as the state of [`timeout_middleware`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestTimeout(pub Duration);
+
+/// Inserted in the request extensions by the outermost [`timeout_middleware`]
+/// so inner ones replace its timeout instead of starting their own.
+#[derive(Debug, Clone)]
+struct TimeoutOverride(watch::Sender<Duration>);
+
+/// Answer with `504 Gateway Timeout` (`E_TIMEOUT`) when the rest of the
+/// request takes longer than the configured [`RequestTimeout`].
+///
+/// Apply it to the whole router with `layer`, and to specific routes with
+/// `route_layer` to override the timeout. The innermost timeout replaces the
+/// outer ones, whether it is longer or shorter, and is still counted from the
+/// start of the request.
+///
+/// ```rust,no_run
+/// # use std::time::Duration;
+/// # use axum::{Router, middleware, routing::{get, post}};
+/// # use beep_server::http::timeout::{RequestTimeout, timeout_middleware};
+/// # async fn upload() {}
+/// # async fn list() {}
+/// let app: Router = Router::new()
+///     .route("/uploads", post(upload))
+///     .route_layer(middleware::from_fn_with_state(
+///         RequestTimeout(Duration::from_secs(120)),
+///         timeout_middleware,
+///     ))
+///     .route("/channels", get(list))
+///     .layer(middleware::from_fn_with_state(
+///         RequestTimeout(Duration::from_secs(10)),
+///         timeout_middleware,
+///     ));
+/// ```
+pub async fn timeout_middleware(
+    State(RequestTimeout(timeout)): State<RequestTimeout>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if let Some(TimeoutOverride(sender)) = req.extensions().get::<TimeoutOverride>() {
+        sender.send_replace(timeout);
+        return Ok(next.run(req).await);
+    }
+
+    let path = req.uri().path().to_string();
+    let start = Instant::now();
+    let (sender, mut receiver) = watch::channel(timeout);
+    req.extensions_mut().insert(TimeoutOverride(sender));
+
+    let mut timeout = timeout;
+    let response = next.run(req);
+    tokio::pin!(response);
+
+    loop {
+        tokio::select! {
+            response = &mut response => return Ok(response),
+            _ = tokio::time::sleep_until(start + timeout) => break,
+            Ok(()) = receiver.changed() => timeout = *receiver.borrow_and_update(),
+        }
+    }
+
+    warn!("timeout middleware: {path} timed out after {timeout:?}");
+    Err(ApiError::Timeout { timeout })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+        middleware::from_fn_with_state,
+        routing::get,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::http::timeout::{RequestTimeout, timeout_middleware};
+
+    /// A route answering after `delay`, with a `route` timeout nested in an
+    /// `outer` one.
+    fn router(delay: Duration, route: Option<Duration>, outer: Duration) -> Router {
+        let mut router = Router::new().route(
+            "/",
+            get(move || async move {
+                tokio::time::sleep(delay).await;
+                "done"
+            }),
+        );
+        if let Some(route) = route {
+            router = router.route_layer(from_fn_with_state(
+                RequestTimeout(route),
+                timeout_middleware,
+            ));
+        }
+
+        router.layer(from_fn_with_state(
+            RequestTimeout(outer),
+            timeout_middleware,
+        ))
+    }
+
+    async fn send(router: Router) -> (StatusCode, Vec<u8>) {
+        let response = router
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        (status, body.to_vec())
+    }
+
+    #[tokio::test]
+    async fn test_timeout_returns_504_body() {
+        let (status, body) = send(router(
+            Duration::from_secs(5),
+            None,
+            Duration::from_millis(20),
+        ))
+        .await;
+        let body = String::from_utf8(body).unwrap();
+
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+        assert!(body.contains("E_TIMEOUT"));
+        assert!(body.contains("request timed out after 20ms"));
+    }
+
+    #[tokio::test]
+    async fn test_route_timeout_extends_outer_timeout() {
+        let (status, body) = send(router(
+            Duration::from_millis(100),
+            Some(Duration::from_secs(5)),
+            Duration::from_millis(20),
+        ))
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, b"done");
+    }
+
+    #[tokio::test]
+    async fn test_route_timeout_shortens_outer_timeout() {
+        let (status, body) = send(router(
+            Duration::from_secs(5),
+            Some(Duration::from_millis(20)),
+            Duration::from_secs(10),
+        ))
+        .await;
+
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+        assert!(String::from_utf8(body).unwrap().contains("after 20ms"));
+    }
+}
@@ -1,37 +1,252 @@
-use std::net::{SocketAddr, ToSocketAddrs};
+use std::{
+    net::{SocketAddr, TcpListener, ToSocketAddrs},
+    time::Duration,
+};
 
 use axum::{Json, Router, http::StatusCode, response::IntoResponse};
+use axum_server::{Handle, tls_rustls::RustlsConfig};
+use beep_auth::AuthError;
 use serde::Serialize;
 use thiserror::Error;
-use tracing::{error, info};
+use tracing::{error, info, warn};
+
+use crate::config::TlsConfig;
 
 pub mod args;
 pub mod config;
 pub mod http;
 
-pub async fn get_addr(host: &str, port: u16) -> Result<SocketAddr, Box<dyn std::error::Error>> {
-    let addrs = format!("{}:{}", host, port)
-        .to_socket_addrs()?
+/// Address family to favour when a host resolves to both IPv4 and IPv6.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IpPreference {
+    /// Keep the resolver order.
+    #[default]
+    Any,
+    /// IPv4 addresses first, IPv6 kept as fallback.
+    Ipv4,
+    /// IPv6 addresses first, IPv4 kept as fallback.
+    Ipv6,
+}
+
+#[derive(Debug, Error)]
+pub enum ResolveError {
+    #[error("failed to resolve {host}: {source}")]
+    Dns {
+        host: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("no socket addresses found for {host}")]
+    NoAddresses { host: String },
+}
+
+/// Resolve `host:port` to every socket address, the `preference` family
+/// first. The relative resolver order is kept within each family.
+///
+/// Resolution runs on tokio's blocking pool, so a slow DNS server does not
+/// stall the runtime.
+pub async fn get_addrs(
+    host: &str,
+    port: u16,
+    preference: IpPreference,
+) -> Result<Vec<SocketAddr>, ResolveError> {
+    let mut addrs = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|source| ResolveError::Dns {
+            host: host.to_string(),
+            source,
+        })?
         .collect::<Vec<SocketAddr>>();
 
-    let socket = match addrs.first() {
-        Some(addr) => *addr,
-        None => return Err("No socket addresses found".into()),
+    if addrs.is_empty() {
+        return Err(ResolveError::NoAddresses {
+            host: host.to_string(),
+        });
+    }
+
+    match preference {
+        IpPreference::Any => {}
+        IpPreference::Ipv4 => addrs.sort_by_key(|addr| !addr.is_ipv4()),
+        IpPreference::Ipv6 => addrs.sort_by_key(|addr| !addr.is_ipv6()),
+    }
+
+    Ok(addrs)
+}
+
+/// Resolve `host:port` to a single socket address of the `preference`
+/// family when available.
+pub async fn get_addr(
+    host: &str,
+    port: u16,
+    preference: IpPreference,
+) -> Result<SocketAddr, ResolveError> {
+    let addrs = get_addrs(host, port, preference).await?;
+
+    Ok(addrs[0])
+}
+
+/// Resolve once the process receives SIGINT (Ctrl+C) or, on Unix, SIGTERM.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("failed to listen for ctrl+c: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
     };
 
-    Ok(socket)
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
 
-pub async fn run_server(addr: SocketAddr, router: Router) {
+/// Serve `router` on `addrs` until SIGINT or SIGTERM.
+///
+/// Each address is tried in turn and the first one that binds is served,
+/// e.g. the output of [`get_addrs`] as a slice.
+///
+/// On signal the server stops accepting connections and gives in-flight
+/// requests up to `grace_period` to complete before returning, so the caller
+/// can then flush telemetry (e.g. `OtelGuard::shutdown`).
+pub async fn run_server(
+    addrs: impl ToSocketAddrs,
+    router: Router,
+    grace_period: Duration,
+) -> Result<(), ServerError> {
+    let (listener, addr) = bind(addrs)?;
+
     info!("listening on {addr}");
 
-    if let Err(e) = axum_server::bind(addr)
+    axum_server::from_tcp(listener)
+        .handle(shutdown_handle(grace_period))
+        .serve(router.into_make_service())
+        .await
+        .map_err(|e| {
+            error!("server error: {}", e);
+            ServerError::Serve(e)
+        })
+}
+
+/// Same as [`run_server`], terminating TLS with the certificate chain and
+/// private key of `tls`.
+///
+/// The certificate and key are loaded before binding, so unreadable,
+/// invalid or mismatched files fail fast with [`ServerError::Tls`].
+pub async fn run_server_tls(
+    addrs: impl ToSocketAddrs,
+    router: Router,
+    grace_period: Duration,
+    tls: &TlsConfig,
+) -> Result<(), ServerError> {
+    let rustls_config = load_tls_config(tls).await?;
+
+    let (listener, addr) = bind(addrs)?;
+
+    info!("listening on {addr} (tls)");
+
+    axum_server::from_tcp_rustls(listener, rustls_config)
+        .handle(shutdown_handle(grace_period))
         .serve(router.into_make_service())
         .await
-    {
-        error!("server error: {}", e);
-        std::process::exit(1);
+        .map_err(|e| {
+            error!("server error: {}", e);
+            ServerError::Serve(e)
+        })
+}
+
+/// Bind the first address of `addrs` that accepts a listener. The error of
+/// the last attempt is returned when none does.
+fn bind(addrs: impl ToSocketAddrs) -> Result<(TcpListener, SocketAddr), ServerError> {
+    let mut last_error = None;
+
+    for addr in addrs.to_socket_addrs().map_err(ServerError::Resolve)? {
+        match TcpListener::bind(addr) {
+            Ok(listener) => return Ok((listener, addr)),
+            Err(source) => {
+                warn!("failed to bind {addr}: {source}");
+                last_error = Some(ServerError::Bind { addr, source });
+            }
+        }
     }
+
+    Err(last_error.unwrap_or(ServerError::NoAddress))
+}
+
+async fn load_tls_config(tls: &TlsConfig) -> Result<RustlsConfig, ServerError> {
+    let read = |path: &std::path::Path| {
+        std::fs::read(path).map_err(|e| ServerError::Tls {
+            message: format!("failed to read {}: {}", path.display(), e),
+        })
+    };
+
+    let cert = read(&tls.cert_path)?;
+    let key = read(&tls.key_path)?;
+
+    RustlsConfig::from_pem(cert, key)
+        .await
+        .map_err(|e| ServerError::Tls {
+            message: format!(
+                "invalid certificate {} or key {}: {}",
+                tls.cert_path.display(),
+                tls.key_path.display(),
+                e
+            ),
+        })
+}
+
+/// Handle triggering a graceful shutdown of the server on SIGINT/SIGTERM.
+fn shutdown_handle(grace_period: Duration) -> Handle {
+    let handle = Handle::new();
+
+    tokio::spawn({
+        let handle = handle.clone();
+        async move {
+            shutdown_signal().await;
+            warn!("shutdown signal received, draining connections for up to {grace_period:?}");
+            handle.graceful_shutdown(Some(grace_period));
+        }
+    });
+
+    handle
+}
+
+#[derive(Debug, Error)]
+pub enum ServerError {
+    #[error("failed to resolve listen address: {0}")]
+    Resolve(#[source] std::io::Error),
+
+    #[error("no address to listen on")]
+    NoAddress,
+
+    #[error("failed to bind {addr}: {source}")]
+    Bind {
+        addr: SocketAddr,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("server error: {0}")]
+    Serve(#[source] std::io::Error),
+
+    #[error("tls: {message}")]
+    Tls { message: String },
 }
 
 #[derive(Debug, Error)]
@@ -44,6 +259,45 @@ pub enum ApiError {
 
     #[error("invalid token: {message}")]
     InvalidToken { message: String },
+
+    #[error("token expired")]
+    TokenExpired,
+
+    #[error("identity provider unavailable: {message}")]
+    IdpUnavailable { message: String },
+
+    #[error("request timed out after {timeout:?}")]
+    Timeout { timeout: Duration },
+
+    #[error("payload too large: limit is {limit} bytes")]
+    PayloadTooLarge { limit: usize },
+
+    #[error("invalid or unknown tenant")]
+    InvalidTenant,
+
+    #[error("{service} is unavailable")]
+    ServiceUnavailable { service: String },
+}
+
+impl From<AuthError> for ApiError {
+    fn from(error: AuthError) -> Self {
+        match error {
+            AuthError::TokenNotFound => ApiError::TokenNotFound,
+            AuthError::Expired => ApiError::TokenExpired,
+            AuthError::InvalidToken { message } => ApiError::InvalidToken { message },
+            AuthError::InvalidAudience { .. } | AuthError::KeyNotFound { .. } => {
+                ApiError::InvalidToken {
+                    message: error.to_string(),
+                }
+            }
+            AuthError::Network { message } => ApiError::IdpUnavailable { message },
+            AuthError::Internal { .. }
+            | AuthError::MissingClientCredentials
+            | AuthError::TokenEndpoint { .. } => ApiError::Unknown {
+                message: error.to_string(),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
@@ -85,6 +339,184 @@ impl IntoResponse for ApiError {
                 }),
             )
                 .into_response(),
+
+            ApiError::TokenExpired => (
+                StatusCode::UNAUTHORIZED,
+                Json(ApiErrorResponse {
+                    code: "E_TOKEN_EXPIRED".to_string(),
+                    status: 401,
+                    message: "token expired".to_string(),
+                }),
+            )
+                .into_response(),
+
+            ApiError::IdpUnavailable { message } => {
+                // The detail may name internal hosts: keep it in the logs.
+                error!("identity provider unavailable: {message}");
+
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(ApiErrorResponse {
+                        code: "E_IDP_UNAVAILABLE".to_string(),
+                        status: 503,
+                        message: "identity provider unavailable".to_string(),
+                    }),
+                )
+                    .into_response()
+            }
+
+            ApiError::Timeout { timeout } => (
+                StatusCode::GATEWAY_TIMEOUT,
+                Json(ApiErrorResponse {
+                    code: "E_TIMEOUT".to_string(),
+                    status: 504,
+                    message: format!("request timed out after {timeout:?}"),
+                }),
+            )
+                .into_response(),
+
+            ApiError::PayloadTooLarge { limit } => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                Json(ApiErrorResponse {
+                    code: "E_PAYLOAD_TOO_LARGE".to_string(),
+                    status: 413,
+                    message: format!("payload too large: limit is {limit} bytes"),
+                }),
+            )
+                .into_response(),
+
+            ApiError::InvalidTenant => (
+                StatusCode::BAD_REQUEST,
+                Json(ApiErrorResponse {
+                    code: "E_INVALID_TENANT".to_string(),
+                    status: 400,
+                    message: "invalid or unknown tenant".to_string(),
+                }),
+            )
+                .into_response(),
+
+            ApiError::ServiceUnavailable { service } => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ApiErrorResponse {
+                    code: "E_SERVICE_UNAVAILABLE".to_string(),
+                    status: 503,
+                    message: format!("{service} is unavailable"),
+                }),
+            )
+                .into_response(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{SocketAddr, TcpListener},
+        time::Duration,
+    };
+
+    use axum::Router;
+
+    use beep_auth::AuthError;
+
+    use crate::{
+        ApiError, IpPreference, ResolveError, ServerError, config::TlsConfig, get_addrs,
+        run_server, run_server_tls,
+    };
+
+    #[test]
+    fn test_api_error_from_auth_error() {
+        assert!(matches!(
+            ApiError::from(AuthError::TokenNotFound),
+            ApiError::TokenNotFound
+        ));
+        assert!(matches!(
+            ApiError::from(AuthError::Expired),
+            ApiError::TokenExpired
+        ));
+        assert!(matches!(
+            ApiError::from(AuthError::KeyNotFound {
+                key: "kid".to_string()
+            }),
+            ApiError::InvalidToken { .. }
+        ));
+        assert!(matches!(
+            ApiError::from(AuthError::Network {
+                message: "connection refused".to_string()
+            }),
+            ApiError::IdpUnavailable { message } if message == "connection refused"
+        ));
+        assert!(matches!(
+            ApiError::from(AuthError::MissingClientCredentials),
+            ApiError::Unknown { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_addrs_orders_by_preference() {
+        let addrs = get_addrs("localhost", 8080, IpPreference::Ipv4)
+            .await
+            .unwrap();
+        let first_v6 = addrs.iter().position(SocketAddr::is_ipv6);
+        let last_v4 = addrs.iter().rposition(SocketAddr::is_ipv4);
+
+        if let (Some(first_v6), Some(last_v4)) = (first_v6, last_v4) {
+            assert!(last_v4 < first_v6);
+        }
+        assert!(addrs.iter().all(|addr| addr.port() == 8080));
+    }
+
+    #[tokio::test]
+    async fn test_get_addrs_dns_failure() {
+        let result = get_addrs("host.invalid", 8080, IpPreference::Any).await;
+
+        assert!(matches!(result, Err(ResolveError::Dns { host, .. }) if host == "host.invalid"));
+    }
+
+    #[tokio::test]
+    async fn test_run_server_tries_each_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let taken = listener.local_addr().unwrap();
+        let addrs = [taken, "127.0.0.1:0".parse().unwrap()];
+
+        let result = tokio::time::timeout(
+            Duration::from_millis(100),
+            run_server(&addrs[..], Router::new(), Duration::from_secs(1)),
+        )
+        .await;
+
+        assert!(result.is_err(), "server should still be serving");
+    }
+
+    #[tokio::test]
+    async fn test_run_server_without_addresses() {
+        let result = run_server(&[][..], Router::new(), Duration::from_secs(1)).await;
+
+        assert!(matches!(result, Err(ServerError::NoAddress)));
+    }
+
+    #[tokio::test]
+    async fn test_run_server_returns_bind_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let result = run_server(addr, Router::new(), Duration::from_secs(1)).await;
+
+        assert!(matches!(result, Err(ServerError::Bind { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_run_server_tls_fails_fast_on_missing_files() {
+        let tls = TlsConfig {
+            cert_path: "/nonexistent/cert.pem".into(),
+            key_path: "/nonexistent/key.pem".into(),
+        };
+
+        let result =
+            run_server_tls("127.0.0.1:0", Router::new(), Duration::from_secs(1), &tls).await;
+
+        assert!(
+            matches!(result, Err(ServerError::Tls { message }) if message.contains("cert.pem"))
+        );
+    }
+}
@@ -1,4 +1,86 @@
-use clap::Parser;
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use clap::{Parser, ValueEnum};
+use opentelemetry_otlp::Compression;
+
+use crate::domain::models::errors::TelemetryError;
+
+/// Output format of the stdout log layer.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable output (the `tracing_subscriber` default formatter).
+    #[default]
+    Pretty,
+    /// Condensed single-line output.
+    Compact,
+    /// Newline-delimited JSON, including span fields and the current span list.
+    Json,
+}
+
+/// Where logs are written.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogOutput {
+    /// Standard output only.
+    #[default]
+    Stdout,
+    /// Daily rotated files in `log_dir` only.
+    File,
+    /// Both standard output and daily rotated files.
+    Both,
+}
+
+/// Deployment environment reported as the `deployment.environment.name`
+/// resource attribute.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeploymentEnvironment {
+    #[default]
+    Develop,
+    Staging,
+    Production,
+}
+
+impl DeploymentEnvironment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeploymentEnvironment::Develop => "develop",
+            DeploymentEnvironment::Staging => "staging",
+            DeploymentEnvironment::Production => "production",
+        }
+    }
+}
+
+impl fmt::Display for DeploymentEnvironment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Case-insensitive, so `Production` and `production` both parse. Anything
+/// else is rejected rather than mislabelling the telemetry.
+impl FromStr for DeploymentEnvironment {
+    type Err = TelemetryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "develop" => Ok(DeploymentEnvironment::Develop),
+            "staging" => Ok(DeploymentEnvironment::Staging),
+            "production" => Ok(DeploymentEnvironment::Production),
+            _ => Err(TelemetryError::Config(format!(
+                "invalid deployment environment `{s}`: expected develop, staging or production"
+            ))),
+        }
+    }
+}
+
+/// Whether telemetry is exported.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TelemetryMode {
+    /// Export traces and metrics through OTLP.
+    #[default]
+    Otlp,
+    /// Skip all exporters and only log to stdout, e.g. in tests.
+    Disabled,
+}
 
 /// Configuration used by the telemetry-enabled application.
 #[derive(Parser)]
@@ -9,4 +91,232 @@ pub struct Config {
 
     #[clap(env, long, default_value = "beep.com", help = "Allowed origins")]
     pub origins: Vec<String>,
+
+    #[clap(
+        env,
+        long,
+        value_enum,
+        default_value_t = LogFormat::Pretty,
+        help = "Format of the stdout logs"
+    )]
+    pub log_format: LogFormat,
+
+    #[clap(
+        env,
+        long,
+        value_enum,
+        default_value_t = LogOutput::Stdout,
+        help = "Where logs are written"
+    )]
+    pub log_output: LogOutput,
+
+    #[clap(
+        env,
+        long,
+        default_value = "logs",
+        help = "Directory of the rotated log files, when `log_output` includes files"
+    )]
+    pub log_dir: PathBuf,
+
+    /// Prefix of the log file names, suffixed with the rotation date, e.g.
+    /// `beep.log.2025-01-31`.
+    #[clap(
+        env,
+        long,
+        default_value = "beep.log",
+        help = "Prefix of the rotated log file names"
+    )]
+    pub log_file_prefix: String,
+
+    #[clap(
+        env,
+        long,
+        value_delimiter = ',',
+        value_parser = parse_header,
+        hide_env_values = true,
+        help = "Headers sent with every OTLP export request, as `key=value` pairs"
+    )]
+    pub otlp_headers: Vec<(String, String)>,
+
+    /// Compression of OTLP export payloads (`gzip`). Trades exporter CPU for
+    /// network bandwidth; disabled by default.
+    #[clap(env, long, help = "Compression of OTLP export payloads (gzip)")]
+    pub compression: Option<Compression>,
+
+    #[clap(
+        env,
+        long,
+        value_enum,
+        default_value_t = TelemetryMode::Otlp,
+        help = "Whether traces and metrics are exported"
+    )]
+    pub telemetry_mode: TelemetryMode,
+
+    #[clap(
+        env,
+        long,
+        default_value_t = DeploymentEnvironment::Develop,
+        value_parser = DeploymentEnvironment::from_str,
+        help = "Deployment environment reported with the telemetry (develop, staging or production)"
+    )]
+    pub deployment_environment: DeploymentEnvironment,
+
+    /// Interval between two periodic metric exports, in seconds. Short-lived
+    /// jobs should rather rely on [`OtelGuard::force_flush`](crate::OtelGuard::force_flush),
+    /// which `shutdown` calls.
+    #[clap(
+        env,
+        long,
+        default_value_t = 30,
+        help = "Interval between two metric exports, in seconds"
+    )]
+    pub metrics_export_interval: u64,
+
+    #[clap(
+        env,
+        long,
+        default_value_t = 1.0,
+        value_parser = parse_ratio,
+        help = "Ratio of root traces sampled, between 0 and 1"
+    )]
+    pub trace_sample_ratio: f64,
+
+    /// Per-target overrides of `trace_sample_ratio`, e.g.
+    /// `payments=1.0,background::poller=0.01`. The longest matching target
+    /// prefix wins. Spans with a sampled parent are always sampled.
+    #[clap(
+        env,
+        long,
+        value_delimiter = ',',
+        value_parser = parse_sample_override,
+        help = "Sampling ratio overrides per target prefix, as `target=ratio` pairs"
+    )]
+    pub trace_sample_overrides: Vec<(String, f64)>,
+
+    /// Span limits bound the memory and export payload of a single span, e.g.
+    /// a handler recording an attribute per item of a huge request. Data past
+    /// a limit is dropped. The defaults are the OpenTelemetry specification
+    /// ones (128).
+    #[clap(
+        env,
+        long,
+        default_value_t = 128,
+        help = "Maximum number of attributes recorded on a span"
+    )]
+    pub max_attributes_per_span: u32,
+
+    #[clap(
+        env,
+        long,
+        default_value_t = 128,
+        help = "Maximum number of events recorded on a span"
+    )]
+    pub max_events_per_span: u32,
+
+    #[clap(
+        env,
+        long,
+        default_value_t = 128,
+        help = "Maximum number of links recorded on a span"
+    )]
+    pub max_links_per_span: u32,
+
+    /// Also expose metrics for Prometheus scraping, see
+    /// [`OtelGuard::prometheus_registry`](crate::OtelGuard::prometheus_registry).
+    #[cfg(feature = "prometheus")]
+    #[clap(env, long, help = "Expose metrics in the Prometheus text format")]
+    pub prometheus: bool,
+}
+
+/// Parse a `key=value` pair into an OTLP header.
+fn parse_header(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("invalid header `{s}`: expected `key=value`"))?;
+
+    Ok((key.trim().to_string(), value.trim().to_string()))
+}
+
+/// Parse a sampling ratio between 0 and 1.
+fn parse_ratio(s: &str) -> Result<f64, String> {
+    let ratio = s
+        .trim()
+        .parse::<f64>()
+        .map_err(|e| format!("invalid ratio `{s}`: {e}"))?;
+
+    if !(0.0..=1.0).contains(&ratio) {
+        return Err(format!(
+            "invalid ratio `{s}`: expected a value between 0 and 1"
+        ));
+    }
+
+    Ok(ratio)
+}
+
+/// Parse a `target=ratio` sampling override.
+fn parse_sample_override(s: &str) -> Result<(String, f64), String> {
+    let (target, ratio) = s
+        .split_once('=')
+        .ok_or_else(|| format!("invalid sampling override `{s}`: expected `target=ratio`"))?;
+
+    Ok((target.trim().to_string(), parse_ratio(ratio)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, Parser};
+
+    use super::{Config, DeploymentEnvironment};
+    use crate::domain::models::errors::TelemetryError;
+
+    #[test]
+    fn test_parse_deployment_environment() {
+        assert_eq!(
+            "production".parse::<DeploymentEnvironment>().unwrap(),
+            DeploymentEnvironment::Production
+        );
+        assert_eq!(
+            "Staging".parse::<DeploymentEnvironment>().unwrap(),
+            DeploymentEnvironment::Staging
+        );
+    }
+
+    #[test]
+    fn test_reject_unknown_deployment_environment() {
+        let result = "prod".parse::<DeploymentEnvironment>();
+
+        assert!(matches!(result, Err(TelemetryError::Config(message)) if message.contains("prod")));
+    }
+
+    #[test]
+    fn test_config_deployment_environment() {
+        let config =
+            Config::try_parse_from(["beep", "--deployment-environment", "staging"]).unwrap();
+
+        assert_eq!(
+            config.deployment_environment,
+            DeploymentEnvironment::Staging
+        );
+        assert!(Config::try_parse_from(["beep", "--deployment-environment", "prod"]).is_err());
+    }
+
+    #[test]
+    fn test_otlp_headers_env_values_hidden_from_help() {
+        let command = Config::command();
+        let argument = command
+            .get_arguments()
+            .find(|arg| arg.get_id() == "otlp_headers")
+            .unwrap();
+
+        assert!(argument.is_hide_env_values_set());
+    }
+
+    #[test]
+    fn test_config_span_limits_default_to_spec() {
+        let config = Config::try_parse_from(["beep", "--max-events-per-span", "16"]).unwrap();
+
+        assert_eq!(config.max_attributes_per_span, 128);
+        assert_eq!(config.max_events_per_span, 16);
+        assert_eq!(config.max_links_per_span, 128);
+    }
 }
@@ -4,4 +4,12 @@ pub enum TelemetryError {
     /// Errors originating from the OpenTelemetry SDK or exporters.
     #[error("OpenTelemetryError: {0}")]
     OpenTelemetry(String),
-}
\ No newline at end of file
+
+    /// Invalid configuration values.
+    #[error("ConfigError: {0}")]
+    Config(String),
+
+    /// Errors installing the global `tracing` subscriber.
+    #[error("SubscriberError: {0}")]
+    Subscriber(String),
+}
@@ -1,5 +1,5 @@
 pub mod config;
 pub mod errors;
 
-pub use config::Config;
+pub use config::{Config, DeploymentEnvironment, LogFormat, LogOutput, TelemetryMode};
 pub use errors::TelemetryError;
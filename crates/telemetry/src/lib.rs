@@ -1,14 +1,19 @@
-
 //! Telemetry integration for the Beep workspace.
 //!
 //! This crate wires up OpenTelemetry (OTLP) tracing and metrics and
 //! provides a small public surface used by other crates to initialize
-//! telemetry. 
+//! telemetry.
 //!
 
-pub mod telemetry;
 pub mod domain;
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+mod sampler;
+pub mod telemetry;
 
-pub use telemetry::{init, OtelGuard};
+pub use telemetry::{LogLevelHandle, OtelGuard, init};
+pub use tracing_core::LevelFilter;
 
-pub use domain::models::errors::TelemetryError;
\ No newline at end of file
+pub use domain::models::errors::TelemetryError;
+#[cfg(feature = "prometheus")]
+pub use prometheus::PrometheusRegistry;
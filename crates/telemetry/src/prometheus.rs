@@ -0,0 +1,49 @@
+//! Prometheus pull exporter, enabled by the `prometheus` feature.
+//!
+//! The exporter is registered as an extra reader on the same meter provider
+//! as the OTLP exporter, so every instrument is exported both ways.
+
+use opentelemetry_sdk::metrics::MeterProviderBuilder;
+use prometheus::{Registry, TEXT_FORMAT, TextEncoder};
+
+use crate::domain::models::errors::TelemetryError;
+
+/// Registry backing the Prometheus scrape endpoint. Cheap to clone.
+#[derive(Clone, Default)]
+pub struct PrometheusRegistry(Registry);
+
+impl PrometheusRegistry {
+    /// Render every collected metric in the Prometheus text format.
+    pub fn render(&self) -> Result<String, TelemetryError> {
+        TextEncoder::new()
+            .encode_to_string(&self.0.gather())
+            .map_err(|e| {
+                TelemetryError::OpenTelemetry(format!("failed to encode Prometheus metrics: {}", e))
+            })
+    }
+
+    /// `Content-Type` of the output of [`PrometheusRegistry::render`].
+    pub fn content_type(&self) -> &'static str {
+        TEXT_FORMAT
+    }
+}
+
+/// Register a Prometheus reader on `builder` when `enabled`.
+pub(crate) fn with_prometheus_reader(
+    builder: MeterProviderBuilder,
+    enabled: bool,
+) -> Result<(MeterProviderBuilder, Option<PrometheusRegistry>), TelemetryError> {
+    if !enabled {
+        return Ok((builder, None));
+    }
+
+    let registry = PrometheusRegistry::default();
+    let exporter = opentelemetry_prometheus::exporter()
+        .with_registry(registry.0.clone())
+        .build()
+        .map_err(|e| {
+            TelemetryError::OpenTelemetry(format!("failed to build Prometheus exporter: {}", e))
+        })?;
+
+    Ok((builder.with_reader(exporter), Some(registry)))
+}
@@ -0,0 +1,169 @@
+//! Trace sampling with per-target ratio overrides.
+
+use std::cmp::Reverse;
+
+use opentelemetry::{
+    Context, KeyValue, Value,
+    trace::{Link, SamplingResult, SpanKind, TraceId},
+};
+use opentelemetry_sdk::trace::{Sampler, ShouldSample};
+
+/// Samples root spans with the ratio of the longest matching target prefix,
+/// or the default ratio when no prefix matches.
+///
+/// The target is read from the `target` attribute set by
+/// `tracing-opentelemetry`. A prefix matches the target itself and its
+/// submodules: `payments` matches `payments` and `payments::api`, but not
+/// `payments_legacy`.
+///
+/// Wrap it in [`Sampler::ParentBased`] so children follow their parent's
+/// decision whatever their own target.
+#[derive(Clone, Debug)]
+pub(crate) struct TargetSampler {
+    /// Sorted by descending prefix length, so the first match is the most
+    /// specific one.
+    overrides: Vec<(String, Sampler)>,
+    default: Sampler,
+}
+
+impl TargetSampler {
+    pub(crate) fn new(default_ratio: f64, overrides: &[(String, f64)]) -> Self {
+        let mut overrides: Vec<(String, Sampler)> = overrides
+            .iter()
+            .map(|(prefix, ratio)| (prefix.clone(), Sampler::TraceIdRatioBased(*ratio)))
+            .collect();
+        overrides.sort_by_key(|(prefix, _)| Reverse(prefix.len()));
+
+        Self {
+            overrides,
+            default: Sampler::TraceIdRatioBased(default_ratio),
+        }
+    }
+
+    fn sampler_for(&self, target: Option<&str>) -> &Sampler {
+        let Some(target) = target else {
+            return &self.default;
+        };
+
+        self.overrides
+            .iter()
+            .find(|(prefix, _)| {
+                target
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map_or(&self.default, |(_, sampler)| sampler)
+    }
+}
+
+impl ShouldSample for TargetSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        let target = attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == "target")
+            .and_then(|kv| match &kv.value {
+                Value::String(target) => Some(target.as_str()),
+                _ => None,
+            });
+
+        self.sampler_for(target).should_sample(
+            parent_context,
+            trace_id,
+            name,
+            span_kind,
+            attributes,
+            links,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::{
+        Context, KeyValue,
+        trace::{
+            SamplingDecision, SpanContext, SpanId, SpanKind, TraceContextExt, TraceFlags, TraceId,
+            TraceState,
+        },
+    };
+    use opentelemetry_sdk::trace::{Sampler, ShouldSample};
+
+    use super::TargetSampler;
+
+    fn sampler() -> TargetSampler {
+        TargetSampler::new(
+            0.0,
+            &[
+                ("payments".to_string(), 1.0),
+                ("payments::poller".to_string(), 0.0),
+            ],
+        )
+    }
+
+    fn decide(
+        sampler: &dyn ShouldSample,
+        parent: Option<&Context>,
+        target: &str,
+    ) -> SamplingDecision {
+        sampler
+            .should_sample(
+                parent,
+                TraceId::from(42),
+                "span",
+                &SpanKind::Internal,
+                &[KeyValue::new("target", target.to_string())],
+                &[],
+            )
+            .decision
+    }
+
+    #[test]
+    fn test_uses_longest_matching_prefix() {
+        let sampler = sampler();
+
+        assert_eq!(
+            decide(&sampler, None, "payments::api"),
+            SamplingDecision::RecordAndSample
+        );
+        assert_eq!(
+            decide(&sampler, None, "payments::poller::job"),
+            SamplingDecision::Drop
+        );
+    }
+
+    #[test]
+    fn test_falls_back_to_default_ratio() {
+        let sampler = sampler();
+
+        assert_eq!(
+            decide(&sampler, None, "payments_legacy"),
+            SamplingDecision::Drop
+        );
+        assert_eq!(decide(&sampler, None, "messages"), SamplingDecision::Drop);
+    }
+
+    #[test]
+    fn test_sampled_parent_takes_precedence() {
+        let sampler = Sampler::ParentBased(Box::new(sampler()));
+        let parent = Context::new().with_remote_span_context(SpanContext::new(
+            TraceId::from(42),
+            SpanId::from(7),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        ));
+
+        assert_eq!(
+            decide(&sampler, Some(&parent), "payments::poller"),
+            SamplingDecision::RecordAndSample
+        );
+    }
+}
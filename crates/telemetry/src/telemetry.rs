@@ -6,100 +6,218 @@
 //!   buffered telemetry.
 //!
 
-use opentelemetry::{global, trace::TracerProvider as _, KeyValue};
+use opentelemetry::{KeyValue, global, trace::TracerProvider as _};
+use opentelemetry_otlp::WithTonicConfig;
 use opentelemetry_sdk::{
+    Resource,
     metrics::{MeterProviderBuilder, PeriodicReader, SdkMeterProvider},
+    propagation::TraceContextPropagator,
     trace::{RandomIdGenerator, Sampler, SdkTracerProvider},
-    Resource,
 };
 use opentelemetry_semantic_conventions::{
-    attribute::{DEPLOYMENT_ENVIRONMENT_NAME, SERVICE_VERSION},
     SCHEMA_URL,
+    attribute::{DEPLOYMENT_ENVIRONMENT_NAME, SERVICE_VERSION},
 };
-use tracing_core::Level;
+use tonic::metadata::{AsciiMetadataKey, AsciiMetadataValue, MetadataMap};
+use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_core::{Level, LevelFilter, Subscriber};
 use tracing_opentelemetry::{MetricsLayer, OpenTelemetryLayer};
+use tracing_subscriber::fmt::MakeWriter;
 use tracing_subscriber::prelude::__tracing_subscriber_SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{Layer, Registry, reload};
 
+use crate::domain::models::config::{Config, LogFormat, LogOutput, TelemetryMode};
 use crate::domain::models::errors::TelemetryError;
-use crate::domain::models::config::Config;
+use crate::sampler::TargetSampler;
 
 /// Build an OpenTelemetry `Resource` describing this service.
-fn resource() -> Resource {
+fn resource(config: &Config) -> Resource {
     Resource::builder()
         .with_service_name(env!("CARGO_PKG_NAME"))
         .with_schema_url(
             [
                 KeyValue::new(SERVICE_VERSION, env!("CARGO_PKG_VERSION")),
-                KeyValue::new(DEPLOYMENT_ENVIRONMENT_NAME, "develop"),
+                KeyValue::new(
+                    DEPLOYMENT_ENVIRONMENT_NAME,
+                    config.deployment_environment.as_str(),
+                ),
             ],
             SCHEMA_URL,
         )
         .build()
 }
 
-/// Initialize and register a meter provider.
-fn init_meter_provider() -> Result<SdkMeterProvider, TelemetryError> {
-    let exporter = opentelemetry_otlp::MetricExporter::builder()
+/// Build the gRPC metadata attached to every OTLP export request.
+///
+/// Header values are never included in errors since they usually carry
+/// credentials.
+fn otlp_metadata(headers: &[(String, String)]) -> Result<MetadataMap, TelemetryError> {
+    let mut metadata = MetadataMap::with_capacity(headers.len());
+
+    for (key, value) in headers {
+        let name = AsciiMetadataKey::from_bytes(key.as_bytes()).map_err(|_| {
+            TelemetryError::OpenTelemetry(format!("invalid OTLP header name: {}", key))
+        })?;
+        let value = AsciiMetadataValue::try_from(value.as_str()).map_err(|_| {
+            TelemetryError::OpenTelemetry(format!("invalid OTLP header value for: {}", key))
+        })?;
+        metadata.append(name, value);
+    }
+
+    Ok(metadata)
+}
+
+/// Build a meter provider exporting via OTLP and to stdout.
+fn meter_provider_builder(config: &Config) -> Result<MeterProviderBuilder, TelemetryError> {
+    let mut builder = opentelemetry_otlp::MetricExporter::builder()
         .with_tonic()
+        .with_metadata(otlp_metadata(&config.otlp_headers)?);
+
+    if let Some(compression) = config.compression {
+        builder = builder.with_compression(compression);
+    }
+
+    let exporter = builder
         .with_temporality(opentelemetry_sdk::metrics::Temporality::default())
         .build()
-        .map_err(|e| TelemetryError::OpenTelemetry(format!("failed to build OTLP metric exporter: {}", e)))?;
+        .map_err(|e| {
+            TelemetryError::OpenTelemetry(format!("failed to build OTLP metric exporter: {}", e))
+        })?;
 
     let reader = PeriodicReader::builder(exporter)
-        .with_interval(std::time::Duration::from_secs(30))
+        .with_interval(std::time::Duration::from_secs(
+            config.metrics_export_interval,
+        ))
         .build();
 
     let stdout_reader =
         PeriodicReader::builder(opentelemetry_stdout::MetricExporter::default()).build();
 
-    let meter_provider = MeterProviderBuilder::default()
-        .with_resource(resource())
+    Ok(MeterProviderBuilder::default()
+        .with_resource(resource(config))
         .with_reader(reader)
-        .with_reader(stdout_reader)
-        .build();
+        .with_reader(stdout_reader))
+}
+
+/// Build and register the global meter provider.
+fn init_meter_provider(builder: MeterProviderBuilder) -> SdkMeterProvider {
+    let meter_provider = builder.build();
 
     global::set_meter_provider(meter_provider.clone());
 
-    Ok(meter_provider)
+    meter_provider
 }
 
 /// Initialize a tracer provider configured to export spans via OTLP.
-fn init_tracer_provider() -> Result<SdkTracerProvider, TelemetryError> {
-    let exporter = opentelemetry_otlp::SpanExporter::builder()
+fn init_tracer_provider(config: &Config) -> Result<SdkTracerProvider, TelemetryError> {
+    let mut builder = opentelemetry_otlp::SpanExporter::builder()
         .with_tonic()
-        .build()
-        .map_err(|e| TelemetryError::OpenTelemetry(format!("failed to build OTLP span exporter: {}", e)))?;
+        .with_metadata(otlp_metadata(&config.otlp_headers)?);
+
+    if let Some(compression) = config.compression {
+        builder = builder.with_compression(compression);
+    }
+
+    let exporter = builder.build().map_err(|e| {
+        TelemetryError::OpenTelemetry(format!("failed to build OTLP span exporter: {}", e))
+    })?;
     Ok(SdkTracerProvider::builder()
-        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
-            1.0,
+        .with_sampler(Sampler::ParentBased(Box::new(TargetSampler::new(
+            config.trace_sample_ratio,
+            &config.trace_sample_overrides,
         ))))
+        .with_max_attributes_per_span(config.max_attributes_per_span)
+        .with_max_events_per_span(config.max_events_per_span)
+        .with_max_links_per_span(config.max_links_per_span)
         .with_id_generator(RandomIdGenerator::default())
-        .with_resource(resource())
+        .with_resource(resource(config))
         .with_batch_exporter(exporter)
         .build())
 }
 
+/// Build a `fmt` layer writing to `writer` in the requested [`LogFormat`].
+///
+/// ANSI colors are only meant for terminals, so disable them for files.
+fn fmt_layer<S, W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(ansi);
+
+    match format {
+        LogFormat::Pretty => layer.boxed(),
+        LogFormat::Compact => layer.compact().boxed(),
+        LogFormat::Json => layer
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed(),
+    }
+}
+
+/// Non-blocking writer to daily rotated files in `log_dir`, `None` unless
+/// [`LogOutput`] includes files.
+///
+/// Fails with [`TelemetryError::Config`] when `log_dir` cannot be created or
+/// the log file cannot be opened.
+fn log_file_writer(config: &Config) -> Result<Option<(NonBlocking, WorkerGuard)>, TelemetryError> {
+    if config.log_output == LogOutput::Stdout {
+        return Ok(None);
+    }
+
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(&config.log_file_prefix)
+        .build(&config.log_dir)
+        .map_err(|e| {
+            TelemetryError::Config(format!(
+                "invalid log directory {}: {}",
+                config.log_dir.display(),
+                e
+            ))
+        })?;
+
+    Ok(Some(tracing_appender::non_blocking(appender)))
+}
+
 /// `tracing` subscriber init to forward traces and metrics to OpenTelemetry (OTLP) and logs to stdout.
 ///
 /// - Initializes and configures an OpenTelemetry tracer provider (OTLP span exporter).
+///   Root spans are sampled with `trace_sample_ratio`, or the ratio of the longest
+///   matching `trace_sample_overrides` target; children follow their parent.
+///   Attributes, events and links past the configured span limits are dropped.
+///   Both exporters send the configured `otlp_headers` with every request and
+///   compress payloads when `compression` is set.
 /// - Initializes and configures an OpenTelemetry meter provider (OTLP metric exporter and
 ///   a stdout metrics reader).
-/// - Builds a `tracing` subscriber registry
+/// - With the `prometheus` feature and `prometheus` set, also registers a Prometheus
+///   reader on the meter provider.
+/// - Skips all providers when `telemetry_mode` is [`TelemetryMode::Disabled`].
+/// - Builds a `tracing` subscriber registry, logging in the configured [`LogFormat`]
+///   to stdout, daily rotated files in `log_dir`, or both depending on [`LogOutput`].
+///   File writes go through a non-blocking writer flushed by [`OtelGuard`].
 ///
 /// Return value
 /// - Success :[`OtelGuard`] owns the tracer and meter providers.
 ///   Before shutting down the application call [`OtelGuard::shutdown`].
 ///
 /// Parameters
-/// - void
+/// - `config`: telemetry configuration
 ///
 /// Example
-/// ```rust
-/// # beep_telemetry::telemetry::{init_tracing_subscriber, OtelGuard};
-/// # beep_telemetry::domain::models::config::Config;
+/// ```rust,no_run
+/// # use beep_telemetry::{init, OtelGuard};
+/// # use beep_telemetry::domain::models::config::Config;
+/// # use clap::Parser;
 /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-/// let guard: OtelGuard = init_tracing_subscriber()?;
+/// let config = Config::parse();
+/// let guard: OtelGuard = init(&config)?;
 ///
 /// // Use `tracing` in the application:
 /// tracing::info!("application started");
@@ -110,43 +228,157 @@ fn init_tracer_provider() -> Result<SdkTracerProvider, TelemetryError> {
 /// # }
 /// ```
 ///
-fn init_tracing_subscriber() -> Result<OtelGuard, TelemetryError> {
-    let tracer_provider = init_tracer_provider()?;
-    let meter_provider = init_meter_provider()?;
+fn init_tracing_subscriber(config: &Config) -> Result<OtelGuard, TelemetryError> {
+    let (tracer_provider, meter_provider) = match config.telemetry_mode {
+        TelemetryMode::Otlp => (
+            Some(init_tracer_provider(config)?),
+            Some(meter_provider_builder(config)?),
+        ),
+        TelemetryMode::Disabled => (None, None),
+    };
+
+    #[cfg(feature = "prometheus")]
+    let (meter_provider, prometheus) = match meter_provider {
+        Some(builder) => {
+            let (builder, registry) =
+                crate::prometheus::with_prometheus_reader(builder, config.prometheus)?;
+            (Some(builder), registry)
+        }
+        None => (None, None),
+    };
 
-    let tracer = tracer_provider.tracer("tracing-otel-subscriber");
+    let meter_provider = meter_provider.map(init_meter_provider);
+
+    let (level_filter, level_handle) = reload::Layer::new(LevelFilter::from_level(Level::INFO));
+
+    let log_to_stdout = matches!(config.log_output, LogOutput::Stdout | LogOutput::Both);
+    let (file_writer, file_guard) = log_file_writer(config)?.unzip();
 
     tracing_subscriber::registry()
-        .with(tracing_subscriber::filter::LevelFilter::from_level(
-            Level::INFO,
-        ))
-        .with(tracing_subscriber::fmt::layer())
-        .with(MetricsLayer::new(meter_provider.clone()))
-        .with(OpenTelemetryLayer::new(tracer))
-        .init();
+        .with(level_filter)
+        .with(log_to_stdout.then(|| fmt_layer(config.log_format, std::io::stdout, true)))
+        .with(file_writer.map(|writer| fmt_layer(config.log_format, writer, false)))
+        .with(meter_provider.clone().map(MetricsLayer::new))
+        .with(
+            tracer_provider.as_ref().map(|provider| {
+                OpenTelemetryLayer::new(provider.tracer("tracing-otel-subscriber"))
+            }),
+        )
+        .try_init()
+        .map_err(|e| TelemetryError::Subscriber(e.to_string()))?;
 
     Ok(OtelGuard {
         tracer_provider,
         meter_provider,
+        log_level: LogLevelHandle(level_handle),
+        _file_guard: file_guard,
+        #[cfg(feature = "prometheus")]
+        prometheus,
     })
 }
 
+/// Cloneable handle changing the log level of the installed subscriber at
+/// runtime, e.g. from an admin endpoint.
+#[derive(Clone)]
+pub struct LogLevelHandle(reload::Handle<LevelFilter, Registry>);
+
+impl LogLevelHandle {
+    /// Replace the current log level.
+    pub fn set_level(&self, level: LevelFilter) -> Result<(), TelemetryError> {
+        self.0
+            .reload(level)
+            .map_err(|e| TelemetryError::Subscriber(format!("failed to reload log level: {}", e)))
+    }
+
+    /// Current log level, if the subscriber is still alive.
+    pub fn level(&self) -> Option<LevelFilter> {
+        self.0.clone_current()
+    }
+}
+
+/// Owns the telemetry providers, `None` when telemetry runs in
+/// [`TelemetryMode::Disabled`], and the writer guard of the log files.
 pub struct OtelGuard {
-    tracer_provider: SdkTracerProvider,
-    meter_provider: SdkMeterProvider,
+    tracer_provider: Option<SdkTracerProvider>,
+    meter_provider: Option<SdkMeterProvider>,
+    log_level: LogLevelHandle,
+    /// Flushes buffered file logs when dropped: keeping it alive for as long
+    /// as the application runs is what keeps file logging going.
+    _file_guard: Option<WorkerGuard>,
+    #[cfg(feature = "prometheus")]
+    prometheus: Option<crate::prometheus::PrometheusRegistry>,
 }
 
 impl OtelGuard {
-    /// Shutdown telemetry providers and flush any buffered telemetry.
+    /// Change the log level at runtime, without restarting the service.
+    pub fn set_level(&self, level: LevelFilter) -> Result<(), TelemetryError> {
+        self.log_level.set_level(level)
+    }
+
+    /// Handle that can be shared with request handlers to change the log
+    /// level after the guard has been stored away.
+    pub fn log_level_handle(&self) -> LogLevelHandle {
+        self.log_level.clone()
+    }
+
+    /// Registry to render on the Prometheus scrape endpoint, `None` unless
+    /// `prometheus` is set and telemetry is enabled.
+    #[cfg(feature = "prometheus")]
+    pub fn prometheus_registry(&self) -> Option<crate::prometheus::PrometheusRegistry> {
+        self.prometheus.clone()
+    }
+
+    /// Export all buffered spans and metrics now, without waiting for the
+    /// next periodic export. Blocks until the exporters are done.
+    ///
+    /// Useful for short-lived jobs exiting before the metric export interval
+    /// elapsed. No-op when telemetry is disabled.
+    pub fn force_flush(&self) -> Result<(), TelemetryError> {
+        let mut errors = Vec::new();
+
+        if let Some(tracer_provider) = &self.tracer_provider
+            && let Err(err) = tracer_provider.force_flush()
+        {
+            errors.push(format!("tracer: {}", err));
+        }
+        if let Some(meter_provider) = &self.meter_provider
+            && let Err(err) = meter_provider.force_flush()
+        {
+            errors.push(format!("meter: {}", err));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(TelemetryError::OpenTelemetry(format!(
+                "failed to flush telemetry: {}",
+                errors.join(", ")
+            )))
+        }
+    }
+
+    /// Flush buffered telemetry with [`Self::force_flush`], then shutdown the
+    /// telemetry providers.
+    ///
+    /// No-op when telemetry is disabled.
     pub async fn shutdown(self) {
-        let tracer_provider = self.tracer_provider;
-        let meter_provider = self.meter_provider;
+        if self.tracer_provider.is_none() && self.meter_provider.is_none() {
+            return;
+        }
 
         let _ = tokio::task::spawn_blocking(move || {
-            if let Err(err) = tracer_provider.shutdown() {
+            if let Err(err) = self.force_flush() {
+                eprintln!("{err}");
+            }
+
+            if let Some(tracer_provider) = self.tracer_provider
+                && let Err(err) = tracer_provider.shutdown()
+            {
                 eprintln!("tracer shutdown error: {err:?}");
             }
-            if let Err(err) = meter_provider.shutdown() {
+            if let Some(meter_provider) = self.meter_provider
+                && let Err(err) = meter_provider.shutdown()
+            {
                 eprintln!("meter shutdown error: {err:?}");
             }
         })
@@ -156,8 +388,112 @@ impl OtelGuard {
 
 /// Initialize telemetry for the application using the provided
 /// [`Config`].
-pub fn init(_config: &Config) -> Result<OtelGuard, TelemetryError> {
-    let guard = init_tracing_subscriber()?;
+///
+/// Also registers the W3C trace context as the global propagator, so
+/// incoming `traceparent` headers can be joined.
+pub fn init(config: &Config) -> Result<OtelGuard, TelemetryError> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let guard = init_tracing_subscriber(config)?;
 
     Ok(guard)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, path::PathBuf};
+
+    use clap::Parser;
+    use tracing_core::LevelFilter;
+    use tracing_subscriber::{prelude::__tracing_subscriber_SubscriberExt, reload};
+
+    use super::{LogLevelHandle, log_file_writer, otlp_metadata};
+    use crate::TelemetryError;
+    use crate::domain::models::config::Config;
+
+    /// Fresh directory under the system temp dir, unique to this test run.
+    fn temp_log_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("beep-telemetry-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn config(output: &str, log_dir: &std::path::Path) -> Config {
+        Config::try_parse_from([
+            "beep",
+            "--log-output",
+            output,
+            "--log-dir",
+            log_dir.to_str().unwrap(),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_otlp_metadata_errors_name_the_key_only() {
+        let headers = [("authorization".to_string(), "Bearer sécret\n".to_string())];
+
+        let Err(TelemetryError::OpenTelemetry(message)) = otlp_metadata(&headers) else {
+            panic!("expected an invalid header value error");
+        };
+
+        assert!(message.contains("authorization"));
+        assert!(!message.contains("sécret"));
+    }
+
+    #[test]
+    fn test_log_file_writer_writes_rotated_files() {
+        for output in ["file", "both"] {
+            let dir = temp_log_dir(output);
+
+            let (mut writer, guard) = log_file_writer(&config(output, &dir)).unwrap().unwrap();
+            writer.write_all(b"hello\n").unwrap();
+            drop(guard);
+
+            let file = std::fs::read_dir(&dir).unwrap().next().unwrap().unwrap();
+            assert!(file.file_name().to_str().unwrap().starts_with("beep.log"));
+            assert_eq!(std::fs::read_to_string(file.path()).unwrap(), "hello\n");
+
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_log_file_writer_skipped_for_stdout() {
+        let dir = temp_log_dir("stdout");
+
+        assert!(log_file_writer(&config("stdout", &dir)).unwrap().is_none());
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_log_file_writer_invalid_directory() {
+        let file = temp_log_dir("not-a-dir");
+        std::fs::write(&file, "").unwrap();
+
+        let result = log_file_writer(&config("file", &file.join("logs")));
+
+        assert!(
+            matches!(result, Err(TelemetryError::Config(message)) if message.contains("not-a-dir"))
+        );
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn test_log_level_handle_reloads_level() {
+        let (level_filter, handle) = reload::Layer::new(LevelFilter::INFO);
+        let subscriber = tracing_subscriber::registry().with(level_filter);
+        let handle = LogLevelHandle(handle);
+
+        handle.set_level(LevelFilter::DEBUG).unwrap();
+        assert_eq!(handle.level(), Some(LevelFilter::DEBUG));
+
+        drop(subscriber);
+        assert_eq!(handle.level(), None);
+        assert!(matches!(
+            handle.set_level(LevelFilter::WARN),
+            Err(TelemetryError::Subscriber(_))
+        ));
+    }
+}